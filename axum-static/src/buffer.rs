@@ -0,0 +1,107 @@
+//! Bounded buffering of response bodies rewritten in memory.
+
+use axum::body::{Body, Bytes};
+use axum::http::{HeaderMap, header};
+use http_body::{Body as _, Frame, SizeHint};
+use std::future::poll_fn;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::digest::DIGEST;
+
+/// Files and bodies larger than this many bytes are streamed as they are rather than
+/// held in memory to be rewritten, hashed or shared.
+pub(crate) const MAX_SIZE: usize = 16 * 1024 * 1024;
+
+/// A body read for rewriting.
+pub(crate) enum Buffered {
+    /// The whole body, at most [`MAX_SIZE`] bytes.
+    Complete(Bytes),
+    /// A body over [`MAX_SIZE`] bytes, with the same contents as the one read.
+    Oversized(Body),
+}
+
+/// Reads `body` into memory, stopping once it exceeds [`MAX_SIZE`] bytes.
+///
+/// Trailers are dropped from bodies read completely.
+pub(crate) async fn read(mut body: Body) -> Result<Buffered, axum::Error> {
+    if http_body::Body::size_hint(&body).lower() > MAX_SIZE as u64 {
+        return Ok(Buffered::Oversized(body));
+    }
+
+    let mut read = Vec::new();
+    while let Some(frame) = poll_fn(|cx| Pin::new(&mut body).poll_frame(cx)).await {
+        let Ok(data) = frame?.into_data() else {
+            continue;
+        };
+        if read.len() + data.len() > MAX_SIZE {
+            read.extend_from_slice(&data);
+            return Ok(Buffered::Oversized(Body::new(Replayed {
+                read: Some(read.into()),
+                rest: body,
+            })));
+        }
+        read.extend_from_slice(&data);
+    }
+    Ok(Buffered::Complete(read.into()))
+}
+
+/// Removes the headers describing the file a rewritten body was made from.
+///
+/// The length, validators and digest of the file don't hold for the rewritten bytes,
+/// which may also change while the file stays the same, and byte ranges of the file
+/// don't line up with them, so `Accept-Ranges` goes too.
+pub(crate) fn drop_validators(headers: &mut HeaderMap) {
+    for name in [
+        header::CONTENT_LENGTH,
+        header::ETAG,
+        header::LAST_MODIFIED,
+        header::ACCEPT_RANGES,
+        DIGEST,
+    ] {
+        headers.remove(name);
+    }
+}
+
+/// Returns the offset of the first occurrence of `needle` in `haystack`.
+pub(crate) fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// A body yielding the bytes already read from it before the rest.
+struct Replayed {
+    read: Option<Bytes>,
+    rest: Body,
+}
+
+impl http_body::Body for Replayed {
+    type Data = Bytes;
+    type Error = axum::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        if let Some(read) = self.read.take() {
+            return Poll::Ready(Some(Ok(Frame::data(read))));
+        }
+        Pin::new(&mut self.rest).poll_frame(cx)
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        let read = self.read.as_ref().map_or(0, |read| read.len() as u64);
+        let rest = http_body::Body::size_hint(&self.rest);
+        let mut hint = SizeHint::new();
+        hint.set_lower(rest.lower() + read);
+        if let Some(upper) = rest.upper() {
+            hint.set_upper(upper + read);
+        }
+        hint
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.read.is_none() && http_body::Body::is_end_stream(&self.rest)
+    }
+}
@@ -0,0 +1,1478 @@
+//! Configurable construction of the static file router.
+
+use axum::{
+    Router,
+    body::Body,
+    http::{HeaderName, HeaderValue, Method, Request, header},
+    middleware::from_fn_with_state,
+    routing::get,
+};
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::cache::{self, MemoryCache};
+use crate::cache_bust::Versions;
+#[cfg(feature = "cdn_fallback")]
+use crate::cdn;
+use crate::coalesce::Flights;
+use crate::digest::DigestAlgorithm;
+use crate::etag::{ContentTags, Manifest};
+use crate::extension::ExtensionStrategy;
+use crate::limit::ConnectionLimit;
+use crate::middleware::{
+    ACCEPT_CH, CROSS_ORIGIN_EMBEDDER_POLICY, CROSS_ORIGIN_OPENER_POLICY, static_middleware,
+};
+use crate::network::IpRange;
+#[cfg(feature = "unicode_normalize")]
+use crate::normalize::NormalizationForm;
+use crate::options::{Callback, DefaultFile, Options};
+use crate::policy::CachePolicy;
+use crate::preload::{self, PreloadLink};
+use crate::resolver::Resolver;
+use crate::serve::Files;
+use crate::source::AsyncFileSource;
+use crate::{etag, mime_types, redirect, status};
+
+/// Builder for a router that serves static files from a directory.
+///
+/// `StaticRouter::new(path).build()` is equivalent to [`static_router`](crate::static_router);
+/// the remaining methods opt into additional behavior.
+///
+/// ## Example
+///
+/// ```rust
+/// use axum_static::StaticRouter;
+///
+/// let app = StaticRouter::new("static/")
+///     .clear_site_data_for_path("/logout", "\"cache\", \"cookies\"")
+///     .build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct StaticRouter {
+    options: Options,
+}
+
+impl StaticRouter {
+    /// Creates a builder that serves static files from the given directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the directory containing static files.
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            options: Options::new(path.as_ref().to_path_buf()),
+        }
+    }
+
+    /// Serves a diagnostic JSON document at `path`, such as `/__status`.
+    ///
+    /// The document reports the crate version, the served directory and which crate
+    /// features are enabled, as
+    /// `{"version":"1.8.6","root":"static/","features":{"cdn_fallback":false,...}}`.
+    /// It is served as `application/json`, even in [maintenance mode](Self::maintenance_mode),
+    /// with the [generated `Cache-Control`](Self::generated_cache_control).
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The request path of the endpoint.
+    #[must_use]
+    pub fn status_endpoint(mut self, path: impl Into<String>) -> Self {
+        let status = DefaultFile {
+            content_type: HeaderValue::from_static("application/json"),
+            body: status::document(&self.options.root).into(),
+        };
+        self.options.status_endpoint = Some((path.into(), status));
+        self
+    }
+
+    /// Answers every request with `503 Service Unavailable` while enabled, except for
+    /// the [status endpoint](Self::status_endpoint).
+    ///
+    /// Combine with [`retry_after`](Self::retry_after) to tell clients when to come back,
+    /// and [`maintenance_allowlist`](Self::maintenance_allowlist) to keep the site
+    /// available to some clients.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether the site is in maintenance.
+    #[must_use]
+    pub fn maintenance_mode(mut self, enabled: bool) -> Self {
+        self.options.maintenance = enabled;
+        self
+    }
+
+    /// Serves clients from the given address ranges normally while in
+    /// [maintenance mode](Self::maintenance_mode).
+    ///
+    /// Clients are identified by their peer address, which requires serving with
+    /// `into_make_service_with_connect_info::<SocketAddr>()`, or by
+    /// [`client_ip_header`](Self::client_ip_header) when behind a proxy. Clients that
+    /// can't be identified get the `503` like everyone else.
+    ///
+    /// # Arguments
+    ///
+    /// * `ranges` - Address ranges in CIDR notation, such as `10.0.0.0/8` or
+    ///   `2001:db8::/32`, or single addresses.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a range is not a valid address or CIDR block.
+    #[must_use]
+    pub fn maintenance_allowlist<I, T>(mut self, ranges: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        self.options
+            .maintenance_allowlist
+            .extend(ranges.into_iter().map(|range| {
+                IpRange::parse(range.as_ref()).expect("invalid maintenance allowlist range")
+            }));
+        self
+    }
+
+    /// Sets `Retry-After` on every `503 Service Unavailable` response the router produces.
+    ///
+    /// The duration is sent as whole delta-seconds.
+    ///
+    /// # Arguments
+    ///
+    /// * `delay` - How long clients should wait before retrying.
+    #[must_use]
+    pub fn retry_after(mut self, delay: Duration) -> Self {
+        self.options.retry_after = Some(HeaderValue::from(delay.as_secs()));
+        self
+    }
+
+    /// Redirects requests for any other host to `host` with `301 Moved Permanently`.
+    ///
+    /// The request's `Host` header is compared case-insensitively, so include the port
+    /// if the canonical host is served on a non-default one. The redirect preserves the
+    /// path and query; requests already on the canonical host are served normally.
+    ///
+    /// # Arguments
+    ///
+    /// * `host` - The canonical host, such as `example.com`.
+    #[must_use]
+    pub fn canonical_host(mut self, host: impl Into<String>) -> Self {
+        self.options.canonical_host = Some(host.into());
+        self
+    }
+
+    /// Sets `Link: <url>; rel="canonical"` on successful HTML responses, with the URL
+    /// formed from `base_url` and the request path.
+    ///
+    /// The query string is left out, and a trailing [index file](Self::index_file) is
+    /// dropped, so `/docs/` and `/docs/index.html` share the canonical
+    /// `https://example.com/docs/`. Paths are taken within the router, so include any
+    /// mount prefix in `base_url` when the router is nested.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - The canonical origin, such as `https://example.com`.
+    #[must_use]
+    pub fn canonical_link(mut self, base_url: impl Into<String>) -> Self {
+        let base_url = base_url.into();
+        self.options.canonical_link = Some(base_url.trim_end_matches('/').to_owned());
+        self
+    }
+
+    /// Redirects requests for exactly `/` to `location` with `302 Found`.
+    ///
+    /// The query string is preserved, and all other requests are served normally. The
+    /// location is sent as given, so include any mount prefix when the router is nested.
+    ///
+    /// # Arguments
+    ///
+    /// * `location` - Where to send root requests, such as `/latest/`.
+    #[must_use]
+    pub fn root_redirect(mut self, location: impl Into<String>) -> Self {
+        self.options.root_redirect = Some(location.into());
+        self
+    }
+
+    /// Loads redirect and rewrite rules from a file, in the style of a `_redirects` file.
+    ///
+    /// Each non-empty line that doesn't start with `#` holds `from to [status]`. Requests
+    /// whose path equals `from` are redirected to `to` with the given status (`301` when
+    /// omitted), or, for status `200`, served from `to` without a redirect. Unmatched
+    /// requests are served normally.
+    ///
+    /// Redirects to a directory are sent with its trailing slash added. A redirect whose
+    /// target is redirected again, such as `/docs/ /docs` when `docs` is a directory, is
+    /// answered with `500 Internal Server Error` rather than a loop.
+    ///
+    /// ```text
+    /// # old blog location
+    /// /blog /posts/ 301
+    /// /app /app/index.html 200
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the redirects file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read or contains a malformed rule.
+    pub fn redirects_file<P: AsRef<Path>>(mut self, path: P) -> io::Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        self.options
+            .redirects
+            .extend(redirect::parse_rules(&contents)?);
+        Ok(self)
+    }
+
+    /// Converts request paths to the Unicode normalization `form` before resolving them,
+    /// so a request for a name in one form finds a file stored in the other.
+    ///
+    /// Redirect and rewrite rules are matched against the normalized path.
+    ///
+    /// # Arguments
+    ///
+    /// * `form` - The form file names are stored in, usually
+    ///   [`NormalizationForm::Nfc`].
+    ///
+    /// # Features
+    ///
+    /// This method is only available when the `unicode_normalize` feature is enabled.
+    #[cfg(feature = "unicode_normalize")]
+    #[must_use]
+    pub fn unicode_normalize(mut self, form: NormalizationForm) -> Self {
+        self.options.unicode_normalize = Some(form);
+        self
+    }
+
+    /// Sets the file served for directory requests. Defaults to `index.html`.
+    ///
+    /// Requests ending in `/` are resolved to this file before serving, so the
+    /// `Content-Type` is inferred from the index file's own extension; an `index.json`
+    /// is served as `application/json` rather than HTML. Conditional requests validate
+    /// against the same file, so `If-Modified-Since` on a directory compares with the
+    /// index file's modification time rather than the directory's.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The file name to serve for directories, such as `index.json`.
+    #[must_use]
+    pub fn index_file(mut self, file: impl Into<String>) -> Self {
+        self.options.index_file = file.into();
+        self
+    }
+
+    /// Serves the root `index.html` app shell for client-side routes.
+    ///
+    /// Precedence for a request is:
+    ///
+    /// 1. A file or directory that exists is served normally, so a directory with an
+    ///    index file serves that index.
+    /// 2. A missing path whose last segment has no extension, such as
+    ///    `/dashboard/settings`, serves `/index.html` with `200 OK`.
+    /// 3. A missing path with an extension, such as `/assets/missing.js`, is a 404.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether unmatched routes serve the app shell.
+    #[must_use]
+    pub fn spa_fallback(mut self, enabled: bool) -> Self {
+        self.options.spa_fallback = enabled;
+        self
+    }
+
+    /// Suggests similarly named files in `404 Not Found` responses.
+    ///
+    /// When a requested file is missing, the directory it would be in is scanned for
+    /// names that extend or are extended by the requested one, or are within two edits
+    /// of it, ignoring case: a request for `/app.jss` suggests `/app.js`. The 404 is
+    /// then an HTML page linking to up to five suggestions. At most a thousand entries
+    /// are examined per request, and plain 404s are kept when nothing is similar.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether suggestions are offered.
+    #[must_use]
+    pub fn suggest_on_404(mut self, enabled: bool) -> Self {
+        self.options.suggest_on_404 = enabled;
+        self
+    }
+
+    /// Refuses to serve paths that aren't regular files.
+    ///
+    /// Reading a named pipe or device file can block indefinitely, so such paths are
+    /// answered with `404 Not Found` instead of being streamed. Checking costs a `stat`
+    /// per request, so it is disabled by default; enable it when other processes can
+    /// create files under the served directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether non-regular files are refused.
+    #[must_use]
+    pub fn regular_files_only(mut self, enabled: bool) -> Self {
+        self.options.regular_files_only = enabled;
+        self
+    }
+
+    /// Serves `content` at `/robots.txt` when the directory has no such file.
+    ///
+    /// The fallback is served as `text/plain`; a real `robots.txt` always takes precedence.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - The robots.txt body, such as `User-agent: *\nDisallow:`.
+    #[must_use]
+    pub fn default_robots_txt(mut self, content: impl Into<String>) -> Self {
+        self.options.default_files.insert(
+            "/robots.txt".to_owned(),
+            DefaultFile {
+                content_type: HeaderValue::from_static("text/plain"),
+                body: content.into().into(),
+            },
+        );
+        self
+    }
+
+    /// Serves `content` at `/.well-known/security.txt` when the directory has no such file.
+    ///
+    /// The fallback is served as `text/plain`; a real `security.txt` always takes
+    /// precedence.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - The security.txt body, such as `Contact: mailto:security@example.com`.
+    #[must_use]
+    pub fn default_security_txt(mut self, content: impl Into<String>) -> Self {
+        self.options.default_files.insert(
+            "/.well-known/security.txt".to_owned(),
+            DefaultFile {
+                content_type: HeaderValue::from_static("text/plain"),
+                body: content.into().into(),
+            },
+        );
+        self
+    }
+
+    /// Answers ACME HTTP-01 challenges from memory, so a certificate can be issued or
+    /// renewed without writing token files into the served directory.
+    ///
+    /// Requests for `/.well-known/acme-challenge/{token}` are answered with the token's
+    /// key authorization as `text/plain`, even in
+    /// [maintenance mode](Self::maintenance_mode); unknown tokens are a 404 rather than a
+    /// file lookup. Calling this again adds to the tokens already configured.
+    ///
+    /// # Arguments
+    ///
+    /// * `tokens` - Pairs of challenge tokens and their key authorizations.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use axum_static::StaticRouter;
+    ///
+    /// let app = StaticRouter::new("static/")
+    ///     .acme_challenge([("LoqXcYV8q5ONbJQxbmR7SCTNo3", "LoqXcYV8q5ONbJQxbmR7SCTNo3.9jg46WB3rR")])
+    ///     .build();
+    /// ```
+    #[must_use]
+    pub fn acme_challenge<I, T, K>(mut self, tokens: I) -> Self
+    where
+        I: IntoIterator<Item = (T, K)>,
+        T: Into<String>,
+        K: Into<String>,
+    {
+        self.options.acme_challenges.extend(tokens.into_iter().map(
+            |(token, key_authorization)| {
+                let key_authorization = DefaultFile {
+                    content_type: HeaderValue::from_static("text/plain"),
+                    body: key_authorization.into().into(),
+                };
+                (token.into(), key_authorization)
+            },
+        ));
+        self
+    }
+
+    /// Sets the `Cache-Control` of responses generated by the router rather than read
+    /// from disk, such as the [status endpoint](Self::status_endpoint) and fallback
+    /// files like [`default_robots_txt`](Self::default_robots_txt).
+    ///
+    /// Defaults to `private, no-store`, since generated content may change between
+    /// requests without any file on disk changing.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The header value, such as `public, max-age=3600`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` is not a valid header value.
+    #[must_use]
+    pub fn generated_cache_control(mut self, value: &str) -> Self {
+        self.options.generated_cache_control =
+            HeaderValue::from_str(value).expect("invalid Cache-Control header value");
+        self
+    }
+
+    /// Serves language variants of files, trying `chain` after the client's preferences.
+    ///
+    /// Variants insert the language before the extension, so a request for
+    /// `/page.html` may be served from `/page.fr.html`. Languages from the
+    /// `Accept-Language` header are tried first, then each language in `chain`, and
+    /// finally the base file. With a chain of `["fr", "en"]`, a French request for a page
+    /// without a French variant is served its English variant if one exists.
+    ///
+    /// Responses carry `Vary: Accept-Language` once negotiation is enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `chain` - The fallback languages, most preferred first.
+    #[must_use]
+    pub fn language_fallback_chain<I, S>(mut self, chain: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.options.language_chain = chain.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Serves AVIF or WebP siblings of images to clients that accept them.
+    ///
+    /// A request for `/photo.jpg` (or `.jpeg`, `.png`, `.gif`) is served from
+    /// `/photo.avif` when the `Accept` header lists `image/avif` and that file exists,
+    /// otherwise from `/photo.webp` for `image/webp`, falling back to the original.
+    /// Formats must be listed explicitly; wildcards like `image/*` don't count. These
+    /// responses carry `Vary: Accept` and the `Content-Type` of the file served.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether image formats are negotiated.
+    #[must_use]
+    pub fn image_negotiation(mut self, enabled: bool) -> Self {
+        self.options.image_negotiation = enabled;
+        self
+    }
+
+    /// Emits a `Clear-Site-Data` header on responses for the given request path.
+    ///
+    /// The path is matched exactly against the request URI path, so a static logout
+    /// landing page served at `/logout` can instruct the browser to drop its caches
+    /// and cookies while still rendering normally.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The request path to match, such as `/logout`.
+    /// * `value` - The header value, such as `"cache", "cookies"`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` is not a valid header value.
+    #[must_use]
+    pub fn clear_site_data_for_path(mut self, path: impl Into<String>, value: &str) -> Self {
+        let value = HeaderValue::from_str(value).expect("invalid Clear-Site-Data header value");
+        self.options.clear_site_data.push((path.into(), value));
+        self
+    }
+
+    /// Announces resources to preload via a `Link` header on HTML responses.
+    ///
+    /// Each link's [`push`](PreloadLink::push) flag controls whether the hint remains
+    /// eligible for HTTP/2 server push.
+    ///
+    /// # Arguments
+    ///
+    /// * `links` - The resources to announce.
+    #[must_use]
+    pub fn preload_links(mut self, links: impl IntoIterator<Item = PreloadLink>) -> Self {
+        let links = links.into_iter().collect::<Vec<_>>();
+        match preload::link_header(&links) {
+            Some(value) => self.options.html_headers.insert(header::LINK, value),
+            None => self.options.html_headers.remove(header::LINK),
+        };
+        self
+    }
+
+    /// Advertises the client hints to send on subsequent requests via `Accept-CH`.
+    ///
+    /// The header is set on HTML responses, such as `Accept-CH: DPR, Width` for
+    /// responsive images.
+    ///
+    /// # Arguments
+    ///
+    /// * `hints` - The client hint header names, such as `DPR` and `Width`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the joined hints are not a valid header value.
+    #[must_use]
+    pub fn accept_ch<I, S>(mut self, hints: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let hints = hints
+            .into_iter()
+            .map(|hint| hint.as_ref().to_owned())
+            .collect::<Vec<_>>();
+        let value = HeaderValue::from_str(&hints.join(", ")).expect("invalid Accept-CH hints");
+        self.options.html_headers.insert(ACCEPT_CH, value);
+        self
+    }
+
+    /// Sets the headers that opt HTML documents into cross-origin isolation.
+    ///
+    /// HTML responses carry `Cross-Origin-Opener-Policy: same-origin` and
+    /// `Cross-Origin-Embedder-Policy: require-corp`, which pages need to use
+    /// `SharedArrayBuffer`.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether the isolation headers are set.
+    #[must_use]
+    pub fn cross_origin_isolation(mut self, enabled: bool) -> Self {
+        let headers = &mut self.options.html_headers;
+        if enabled {
+            headers.insert(
+                CROSS_ORIGIN_OPENER_POLICY,
+                HeaderValue::from_static("same-origin"),
+            );
+            headers.insert(
+                CROSS_ORIGIN_EMBEDDER_POLICY,
+                HeaderValue::from_static("require-corp"),
+            );
+        } else {
+            headers.remove(CROSS_ORIGIN_OPENER_POLICY);
+            headers.remove(CROSS_ORIGIN_EMBEDDER_POLICY);
+        }
+        self
+    }
+
+    /// Serves HTML files as `text/plain` to clients whose `Accept` header prefers it,
+    /// for viewing page source.
+    ///
+    /// The plain-text view is chosen only when `text/plain` has a higher quality than
+    /// `text/html`, so browsers asking for HTML as usual still get rendered pages.
+    /// HTML responses then vary on `Accept`.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether plain-text views are served.
+    #[must_use]
+    pub fn allow_plaintext_view(mut self, enabled: bool) -> Self {
+        self.options.allow_plaintext_view = enabled;
+        self
+    }
+
+    /// Chooses how file names with several extensions are mapped to a content type.
+    ///
+    /// With [`ExtensionStrategy::LastSegment`], the default, `archive.tar.gz` is served
+    /// as `application/gzip`; with [`ExtensionStrategy::MostSpecific`], known compound
+    /// extensions such as `tar.gz` map to their own type, `application/x-gtar`. Either
+    /// way `file.min.js` is served as JavaScript.
+    ///
+    /// # Arguments
+    ///
+    /// * `strategy` - How the extension is chosen.
+    #[must_use]
+    pub fn compound_extension_strategy(mut self, strategy: ExtensionStrategy) -> Self {
+        self.options.extension_strategy = strategy;
+        self
+    }
+
+    /// Reads the `Content-Type` of files whose type can't be inferred from a sidecar.
+    ///
+    /// When a file has no extension or an unknown one, its type is read from the first
+    /// line of `<file>.meta` next to it, so `data` with a `data.meta` holding
+    /// `application/x-custom` is served as `application/x-custom`. Sidecars are
+    /// ordinary files and can be requested themselves.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether sidecars are consulted.
+    #[must_use]
+    pub fn sidecar_mime(mut self, enabled: bool) -> Self {
+        self.options.sidecar_mime = enabled;
+        self
+    }
+
+    /// Labels successful responses with the first media type returned by an ordered
+    /// chain of [resolvers](crate::resolver), taking over content-type inference.
+    ///
+    /// [`content_type_for_extension`](Self::content_type_for_extension),
+    /// [`mime_types_file`](Self::mime_types_file), [`sidecar_mime`](Self::sidecar_mime)
+    /// and [`compound_extension_strategy`](Self::compound_extension_strategy) have no
+    /// effect with a chain; use the equivalent resolvers in it instead. Responses no
+    /// resolver labels keep the type inferred from their extension.
+    ///
+    /// # Arguments
+    ///
+    /// * `resolvers` - The resolvers to consult, in order.
+    #[must_use]
+    pub fn content_type_resolvers(mut self, resolvers: Vec<Box<dyn Resolver>>) -> Self {
+        self.options.content_type_resolvers = Some(Callback(resolvers.into()));
+        self
+    }
+
+    /// Serves files with the given extension using a fixed `Content-Type`.
+    ///
+    /// The override takes precedence over the built-in table and `mime_guess`.
+    ///
+    /// # Arguments
+    ///
+    /// * `extension` - The file extension without the leading dot, such as `map`.
+    /// * `mime` - The MIME type to serve, such as `application/json`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mime` is not a valid header value.
+    #[must_use]
+    pub fn content_type_for_extension(mut self, extension: &str, mime: &str) -> Self {
+        let mime = HeaderValue::from_str(mime).expect("invalid Content-Type header value");
+        self.options
+            .content_types
+            .insert(extension.to_ascii_lowercase(), mime);
+        self
+    }
+
+    /// Loads content types from an Apache-style `mime.types` file, overriding the
+    /// built-in table and `mime_guess` for the extensions it lists.
+    ///
+    /// Each line holds a media type followed by its extensions, and `#` starts a
+    /// comment. When several lines list an extension, the last one wins, and
+    /// [`content_type_for_extension`](Self::content_type_for_extension) calls made after
+    /// this one take precedence.
+    ///
+    /// ```text
+    /// # proprietary formats
+    /// application/x-myapp    myapp
+    /// model/gltf-binary      glb
+    /// text/markdown          md markdown
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the `mime.types` file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read or lists an invalid media type.
+    pub fn mime_types_file<P: AsRef<Path>>(mut self, path: P) -> io::Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        self.options
+            .content_types
+            .extend(mime_types::parse(&contents)?);
+        Ok(self)
+    }
+
+    /// Sets the `Cache-Control` header for files with the given extension.
+    ///
+    /// # Arguments
+    ///
+    /// * `extension` - The file extension without the leading dot, such as `js`.
+    /// * `value` - The header value, such as `public, max-age=31536000, immutable`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` is not a valid header value.
+    #[must_use]
+    pub fn cache_control_for_extension(mut self, extension: &str, value: &str) -> Self {
+        let value = HeaderValue::from_str(value).expect("invalid Cache-Control header value");
+        self.options
+            .cache_control
+            .insert(extension.to_ascii_lowercase(), value);
+        self
+    }
+
+    /// Sets `Cache-Control` by the served file's type, such as `no-cache` for HTML pages
+    /// and, when enabled, `immutable` for fingerprinted assets.
+    ///
+    /// Responses that already carry a `Cache-Control`, including one set by
+    /// [`cache_control_for_extension`](Self::cache_control_for_extension), keep it.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - The `Cache-Control` values by file type.
+    #[must_use]
+    pub fn cache_policy(mut self, policy: CachePolicy) -> Self {
+        self.options.cache_policy = Some(policy);
+        self
+    }
+
+    /// Computes `Cache-Control` from the age of the served file.
+    ///
+    /// The callback receives the time elapsed since the file was last modified and
+    /// returns the header value, so recently changed files can be cached briefly while
+    /// stable ones are cached for longer. It runs per request for successful responses
+    /// that don't already carry a `Cache-Control` header, including one set by
+    /// [`cache_control_for_extension`](Self::cache_control_for_extension).
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - Maps the file's age to a `Cache-Control` value.
+    #[must_use]
+    pub fn cache_by_age<F>(mut self, policy: F) -> Self
+    where
+        F: Fn(Duration) -> String + Send + Sync + 'static,
+    {
+        self.options.cache_by_age = Some(Callback(Arc::new(policy)));
+        self
+    }
+
+    /// Moves the `max-age` of each response's `Cache-Control` by a random offset of up
+    /// to `range` either way, so copies cached at the same time don't all expire at once.
+    ///
+    /// The offset is drawn per response in whole seconds and never takes `max-age`
+    /// below zero; `Cache-Control` values without a `max-age` are left as-is.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - The largest offset applied, such as 60 seconds.
+    #[must_use]
+    pub fn cache_jitter(mut self, range: Duration) -> Self {
+        self.options.cache_jitter = Some(range.as_secs());
+        self
+    }
+
+    /// Calls `callback` when a client disconnects before a response body is fully sent.
+    ///
+    /// The callback receives the served request path and the number of body bytes
+    /// handed to the connection before it closed, such as for logging aborted
+    /// downloads of large files. The open file is released as soon as the connection
+    /// drops the body.
+    ///
+    /// Broken pipes and connection resets, expected when clients go away, are never
+    /// handled as errors. If one surfaces while the file is opened, such as from a
+    /// [`file_source`](Self::file_source), the callback receives 0 bytes and the
+    /// response is an empty `499 Client Closed Request` rather than a 500. Failures
+    /// writing to the connection happen in the server, after the response has left the
+    /// router, so they never reach it as errors; they show up only as the body being
+    /// dropped early, and are reported with the bytes sent so far.
+    ///
+    /// # Arguments
+    ///
+    /// * `callback` - Receives the path and the bytes sent.
+    #[must_use]
+    pub fn on_disconnect<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&str, u64) + Send + Sync + 'static,
+    {
+        self.options.on_disconnect = Some(Callback(Arc::new(callback)));
+        self
+    }
+
+    /// Serves a precompressed `<file>.gz` variant to clients that accept gzip.
+    ///
+    /// The `Content-Type` is still inferred from the requested file, and precompressed
+    /// responses are never compressed again on the fly.
+    #[must_use]
+    pub fn precompressed_gzip(mut self) -> Self {
+        self.options.precompressed_gzip = true;
+        self
+    }
+
+    /// Serves a precompressed `<file>.br` variant to clients that accept brotli.
+    ///
+    /// The `Content-Type` is still inferred from the requested file, and precompressed
+    /// responses are never compressed again on the fly.
+    #[must_use]
+    pub fn precompressed_br(mut self) -> Self {
+        self.options.precompressed_br = true;
+        self
+    }
+
+    /// Serves a precompressed `<file>.zst` variant to clients that accept zstd.
+    ///
+    /// The `Content-Type` is still inferred from the requested file, and precompressed
+    /// responses are never compressed again on the fly. When a client accepts several
+    /// precompressed encodings, the one it prefers by quality value is served.
+    #[must_use]
+    pub fn precompressed_zstd(mut self) -> Self {
+        self.options.precompressed_zstd = true;
+        self
+    }
+
+    /// Serves decompressed bytes when only a precompressed variant of a file exists.
+    ///
+    /// When `<file>` is missing but `<file>.br` or `<file>.gz` exists, clients that
+    /// can't receive that variant directly get its contents decoded as they stream, with
+    /// the `Content-Type` of `<file>`. Clients that accept the coding are still served the
+    /// variant as-is when the matching precompressed option is enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether precompressed-only files are decompressed for other clients.
+    ///
+    /// # Features
+    ///
+    /// This method is only available when the `compression` feature is enabled.
+    #[cfg(feature = "compression")]
+    #[must_use]
+    pub fn auto_decompress(mut self, enabled: bool) -> Self {
+        self.options.auto_decompress = enabled;
+        self
+    }
+
+    /// Gzip-compresses compressible responses on the fly for clients that accept it.
+    ///
+    /// When combined with [`precompressed_gzip`](Self::precompressed_gzip) or
+    /// [`precompressed_br`](Self::precompressed_br), a precompressed variant always wins
+    /// and is served as-is; on-the-fly compression only applies to files without one.
+    /// Images and very small responses are left uncompressed. `HEAD` responses carry
+    /// the `Content-Encoding` and `ETag` the `GET` would.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether gzip compression is enabled.
+    ///
+    /// # Features
+    ///
+    /// This method is only available when the `compression` feature is enabled.
+    #[cfg(feature = "compression")]
+    #[must_use]
+    pub fn compress(mut self, enabled: bool) -> Self {
+        self.options.compression.gzip = enabled;
+        self
+    }
+
+    /// Gzip-compresses files with the given extension on the fly for clients that accept it.
+    ///
+    /// # Arguments
+    ///
+    /// * `extension` - The file extension without the leading dot, such as `map`.
+    ///
+    /// # Features
+    ///
+    /// This method is only available when the `compression` feature is enabled.
+    #[cfg(feature = "compression")]
+    #[must_use]
+    pub fn compress_extension(mut self, extension: &str) -> Self {
+        self.options
+            .compression
+            .gzip_extensions
+            .insert(extension.to_ascii_lowercase());
+        self
+    }
+
+    /// Brotli-compresses responses on the fly for clients that accept `br`.
+    ///
+    /// Only brotli is attempted, and images and very small responses are left
+    /// uncompressed. The `Content-Type` still reflects the original file.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether brotli compression is enabled.
+    ///
+    /// # Features
+    ///
+    /// This method is only available when the `compression` feature is enabled.
+    #[cfg(feature = "compression")]
+    #[must_use]
+    pub fn compress_brotli(mut self, enabled: bool) -> Self {
+        self.options.compression.brotli = enabled;
+        self
+    }
+
+    /// Configures `.map` sourcemaps in one call.
+    ///
+    /// Sourcemaps are served as `application/json` with
+    /// `Cache-Control: public, max-age=<max_age>, immutable`, and are gzip-compressed
+    /// on the fly when the `compression` feature is enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_age` - How long clients may cache sourcemaps.
+    #[must_use]
+    pub fn sourcemap_policy(self, max_age: Duration) -> Self {
+        let cache_control = format!("public, max-age={}, immutable", max_age.as_secs());
+        let router = self
+            .content_type_for_extension("map", "application/json")
+            .cache_control_for_extension("map", &cache_control);
+        #[cfg(feature = "compression")]
+        let router = router.compress_extension("map");
+        router
+    }
+
+    /// Emits a `Server-Timing` header breaking down time spent serving the response.
+    ///
+    /// The header reports `stat` for filesystem lookups made while resolving the served
+    /// file, `stream` for opening the file and producing the streamed response, and
+    /// `infer` for content-type inference, each in milliseconds. Phases that don't run
+    /// for a request, such as `stat` when no option needs to inspect the filesystem,
+    /// are left out.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether the header is emitted.
+    #[must_use]
+    pub fn server_timing(mut self, enabled: bool) -> Self {
+        self.options.server_timing = enabled;
+        self
+    }
+
+    /// Injects a per-request nonce into HTML responses for a nonce-based
+    /// `Content-Security-Policy`.
+    ///
+    /// Every occurrence of `__CSP_NONCE__` in a served HTML document, such as
+    /// `<script nonce="__CSP_NONCE__">`, is replaced by a fresh nonce of 128 random
+    /// bits, and the response carries `Content-Security-Policy: script-src 'nonce-…'`
+    /// with the same value. A policy the response already has is kept, with the nonce
+    /// added to its `script-src` and `style-src` directives. Since each response is unique, these documents are sent
+    /// with `Cache-Control: no-store` and without `ETag`, `Last-Modified` or
+    /// `Accept-Ranges`. Range requests are answered with the whole document, and
+    /// precompressed variants of HTML files are bypassed so every document is nonced.
+    /// Documents over 16 MiB are served as they are, without a nonce.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether nonces are injected.
+    #[must_use]
+    pub fn csp_nonce(mut self, enabled: bool) -> Self {
+        self.options.csp_nonce = enabled;
+        self
+    }
+
+    /// Assembles served HTML from fragment files named by server-side include
+    /// directives, such as `<!--#include file="header.html"-->`.
+    ///
+    /// Paths are relative to the including file; `virtual` paths, as in
+    /// `<!--#include virtual="/partials/nav.html"-->`, may also start from the root.
+    /// Fragments may include further fragments. Directives naming files outside the
+    /// served directory, missing files, or a file that is already being included are
+    /// replaced with `[an error occurred while processing this directive]`. Assembled
+    /// pages carry no validators, since they change whenever a fragment does. Pages
+    /// over 16 MiB are served without processing their directives.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether include directives are processed.
+    #[must_use]
+    pub fn server_side_includes(mut self, enabled: bool) -> Self {
+        self.options.server_side_includes = enabled;
+        self
+    }
+
+    /// Appends a version to local asset references in served HTML, so that
+    /// `src="app.js"` becomes `src="app.js?v=<hash>"` after the file's contents change.
+    ///
+    /// Quoted `src` and `href` attribute values are rewritten, with the first 16 hex
+    /// digits of the referenced file's SHA-256. External URLs, protocol-relative and
+    /// fragment-only references, and references to missing files are left untouched.
+    /// Each referenced file is hashed again only once its size or modification time
+    /// changes. Rewritten pages carry no validators, since they change whenever a
+    /// referenced file does. Pages over 16 MiB are served unversioned.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether asset references are versioned.
+    #[must_use]
+    pub fn cache_bust_html(mut self, enabled: bool) -> Self {
+        self.options.cache_bust_html = enabled.then(Versions::default);
+        self
+    }
+
+    /// Wraps JSON files as JSONP when requested with a `callback` query parameter.
+    ///
+    /// A request for `/data.json?callback=handle` is answered with
+    /// `/**/handle(<file contents>);` as `application/javascript`. Callbacks must be
+    /// JavaScript identifiers, optionally dotted like `app.handle`; any other callback
+    /// is refused with `400 Bad Request` to prevent script injection. `HEAD` requests
+    /// get the JSONP headers without a body, and files over 16 MiB are served unwrapped
+    /// rather than buffered.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether JSONP requests are served.
+    #[must_use]
+    pub fn allow_jsonp(mut self, enabled: bool) -> Self {
+        self.options.allow_jsonp = enabled;
+        self
+    }
+
+    /// Removes the named headers from every response, such as `Server` or `X-Powered-By`
+    /// set by an inner service.
+    ///
+    /// Header names are matched case-insensitively.
+    ///
+    /// # Arguments
+    ///
+    /// * `names` - The names of the headers to remove.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a name is not a valid header name.
+    #[must_use]
+    pub fn strip_headers<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.options
+            .strip_headers
+            .extend(names.into_iter().map(|name| {
+                HeaderName::try_from(name.as_ref()).expect("invalid stripped header name")
+            }));
+        self
+    }
+
+    /// Emits an `ETag` for served files and answers a matching `If-None-Match` with
+    /// `304 Not Modified`.
+    ///
+    /// Tags are derived from each file's size and modification time. Responses that are
+    /// precompressed or compressed on the fly carry the weak form `W/"…"`, since their
+    /// bytes differ from the file's. A range request whose `If-Range` names a different
+    /// or weak tag is answered with the whole file, as weak tags can't validate ranges.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether entity tags are emitted.
+    #[must_use]
+    pub fn etag(mut self, enabled: bool) -> Self {
+        self.options.etag = enabled;
+        self
+    }
+
+    /// Starts every computed [`etag`](Self::etag) with `prefix`, such as `v2-` for
+    /// `"v2-3e8-17f0a2b3c4d5e6f7"`.
+    ///
+    /// Changing the prefix invalidates tags cached before a deploy or by another CDN
+    /// tier. Conditional requests are compared against the prefixed tags.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - The text placed at the start of each tag.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `prefix` contains a `"` or a character not allowed in an entity tag.
+    #[must_use]
+    pub fn etag_prefix(mut self, prefix: impl Into<String>) -> Self {
+        let prefix = prefix.into();
+        assert!(etag::is_valid_prefix(&prefix), "invalid ETag prefix");
+        self.options.etag_prefix = prefix;
+        self
+    }
+
+    /// Derives [`etag`](Self::etag) tags from a SHA-256 of each file's contents instead
+    /// of its size and modification time, so redeploying identical files keeps their
+    /// tags and clients' cached copies valid.
+    ///
+    /// The tags are weak, `W/"…"`, so they also stand for compressed variants, and are
+    /// remembered until the file's size or modification time changes. Files over
+    /// 16 MiB aren't hashed and keep size and modification time tags. Enabling this
+    /// also enables entity tags.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether tags are derived from contents only.
+    #[must_use]
+    pub fn etag_content_only(mut self, enabled: bool) -> Self {
+        self.options.etag_content_only = enabled.then(ContentTags::default);
+        self.options.etag |= enabled;
+        self
+    }
+
+    /// Takes [`etag`](Self::etag) tags from a build manifest mapping file paths to
+    /// content hashes, such as `assets/app.js` to `5d41402a`, so listed files are never
+    /// hashed or tagged from their modification time.
+    ///
+    /// Paths are relative to the root, with or without a leading `/`. Hashes are sent
+    /// as strong tags, `"5d41402a"`, unless already quoted or weak, and ignore the
+    /// [`etag_prefix`](Self::etag_prefix). Files missing from the manifest get the
+    /// computed tag. Enabling this also enables entity tags.
+    ///
+    /// # Arguments
+    ///
+    /// * `manifest` - Pairs of file paths and their hashes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a hash contains a character not allowed in an entity tag.
+    #[must_use]
+    pub fn etag_manifest<I, P, H>(mut self, manifest: I) -> Self
+    where
+        I: IntoIterator<Item = (P, H)>,
+        P: AsRef<str>,
+        H: AsRef<str>,
+    {
+        let tags = self
+            .options
+            .etag_manifest
+            .get_or_insert_with(Manifest::default);
+        for (path, hash) in manifest {
+            assert!(
+                tags.insert(path.as_ref(), hash.as_ref()),
+                "invalid ETag in manifest"
+            );
+        }
+        self.options.etag = true;
+        self
+    }
+
+    /// Emits a `Digest` header (RFC 3230) with the hash of each served file, such as
+    /// `Digest: sha-256=X48E9qOokqqrvdts8nOJRJN3OWDUoyWxBf7kbu9DBPE=`.
+    ///
+    /// Files are hashed per request, so files over 16 MiB are served without the header
+    /// and, with the `tracing` feature, a warning. Content-coded responses and responses
+    /// whose bodies are rewritten, such as for [`csp_nonce`](Self::csp_nonce), carry no
+    /// digest either, since their bytes differ from the file's.
+    ///
+    /// # Arguments
+    ///
+    /// * `algorithm` - The hash algorithm.
+    #[must_use]
+    pub fn digest_header(mut self, algorithm: DigestAlgorithm) -> Self {
+        self.options.digest = Some(algorithm);
+        self
+    }
+
+    /// Answers a `GET` or `HEAD` whose `If-Match` lists no current [`etag`](Self::etag)
+    /// with `412 Precondition Failed`.
+    ///
+    /// Tags are compared strongly, so weak tags in `If-Match` never match; `*` matches
+    /// any existing file. Enabling this also enables entity tags.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether `If-Match` is honored.
+    #[must_use]
+    pub fn honor_if_match(mut self, enabled: bool) -> Self {
+        self.options.honor_if_match = enabled;
+        self.options.etag |= enabled;
+        self
+    }
+
+    /// Disables range requests, for deployments behind caches that mishandle them.
+    ///
+    /// `Range` headers are ignored, so every request is answered with the full file,
+    /// and responses no longer advertise `Accept-Ranges`.
+    ///
+    /// # Arguments
+    ///
+    /// * `disabled` - Whether range requests are disabled.
+    #[must_use]
+    pub fn disable_ranges(mut self, disabled: bool) -> Self {
+        self.options.disable_ranges = disabled;
+        self
+    }
+
+    /// Proxies requests for files missing from the directory to an upstream origin.
+    ///
+    /// A `GET` or `HEAD` for a missing file is forwarded to `base_url` followed by the
+    /// request path and query, so `/app.js` is fetched from
+    /// `https://cdn.example.com/assets/app.js` for a base of
+    /// `https://cdn.example.com/assets`. The upstream status, body and caching headers
+    /// are streamed back. An upstream that can't be reached within 10 seconds, or that
+    /// stops sending for 30 before its headers arrive, yields `502 Bad Gateway`; one that
+    /// stalls mid-body has the body cut short. Paths are forwarded as resolved
+    /// against the directory, so `..` segments never reach the upstream.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - The URL the request path is appended to.
+    ///
+    /// # Features
+    ///
+    /// This method is only available when the `cdn_fallback` feature is enabled.
+    #[cfg(feature = "cdn_fallback")]
+    #[must_use]
+    pub fn cdn_fallback(mut self, base_url: &str) -> Self {
+        self.options.cdn_fallback = Some(cdn::Upstream::new(base_url));
+        self
+    }
+
+    /// Serves files from a custom [`AsyncFileSource`], such as an object store, instead
+    /// of the directory passed to [`new`](Self::new).
+    ///
+    /// Responses from the source get the same content types, headers and caching as
+    /// files from disk, and a [failover root](Self::failover_root) still takes over on
+    /// IO errors. Options that look files up on disk directly, such as
+    /// [`etag`](Self::etag), [`spa_fallback`](Self::spa_fallback),
+    /// [`memory_cache`](Self::memory_cache) and directory redirects, keep consulting
+    /// that directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - Where files are read from.
+    #[must_use]
+    pub fn file_source(mut self, source: impl AsyncFileSource) -> Self {
+        self.options.file_source = Some(Callback(Arc::new(source)));
+        self
+    }
+
+    /// Serves files from `assets`, usually [`EmbeddedAssets`](crate::EmbeddedAssets)
+    /// compiled into the binary, when they are missing from disk or the directory can't
+    /// be read, before answering with a `404` or an IO error.
+    ///
+    /// This keeps a minimal site, such as error and maintenance pages, available when
+    /// the static directory is absent, as on a fresh deploy or an unmounted volume. The
+    /// fallback is consulted after the [failover root](Self::failover_root).
+    ///
+    /// # Arguments
+    ///
+    /// * `assets` - The assets served when disk has no file.
+    #[must_use]
+    pub fn embedded_fallback(mut self, assets: impl AsyncFileSource) -> Self {
+        self.options.embedded_fallback = Some(Callback(Arc::new(assets)));
+        self
+    }
+
+    /// Serves files from `path` when the primary directory fails with an IO error,
+    /// such as when a network mount goes down.
+    ///
+    /// The failover is tried after the primary exhausts its
+    /// [`read_retries`](Self::read_retries) and before errors are handled. A file
+    /// missing from the primary is not an error and is not looked up in the failover.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the directory holding a copy of the static files.
+    #[must_use]
+    pub fn failover_root<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.options.failover_root = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Retries opening a file up to `retries` times when it fails with a transient IO
+    /// error, such as `Interrupted` on flaky network storage. Defaults to no retries.
+    ///
+    /// Errors that persist through every retry are handled as usual, by the
+    /// `handle_error` handler when that feature is enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `retries` - The number of retries after the first attempt.
+    #[must_use]
+    pub fn read_retries(mut self, retries: u32) -> Self {
+        self.options.read_retries = retries;
+        self
+    }
+
+    /// Answers IO errors that persist through every [retry](Self::read_retries) with a
+    /// JSON body instead of the `handle_error` text or an empty body.
+    ///
+    /// The body carries the status and the IO error kind, such as
+    /// `{"status":500,"error":"Internal Server Error","kind":"PermissionDenied"}`, and
+    /// never the error message, which may name file paths.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether IO errors are answered with JSON.
+    #[must_use]
+    pub fn json_errors(mut self, enabled: bool) -> Self {
+        self.options.json_errors = enabled;
+        self
+    }
+
+    /// Limits each client to `max` requests in flight at once, answering any more with
+    /// `429 Too Many Requests`.
+    ///
+    /// Clients are identified by their peer address, which requires serving the app
+    /// with `into_make_service_with_connect_info::<SocketAddr>()`, or by the
+    /// [`client_ip_header`](Self::client_ip_header) when behind a proxy. Requests from
+    /// clients that can't be identified are not limited. A request holds its slot until
+    /// its response body has been sent or dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `max` - The number of concurrent requests allowed per client address.
+    #[must_use]
+    pub fn max_connections_per_ip(mut self, max: usize) -> Self {
+        self.options.connection_limit = Some(ConnectionLimit::new(max));
+        self
+    }
+
+    /// Identifies clients for [`max_connections_per_ip`](Self::max_connections_per_ip)
+    /// and [`maintenance_allowlist`](Self::maintenance_allowlist) by a header set by a
+    /// trusted proxy, such as `X-Forwarded-For`, instead of the peer address.
+    ///
+    /// The last address in the header is used, being the one appended by the proxy
+    /// directly in front of the router; earlier entries can be forged by clients.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the header carrying the client address.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` is not a valid header name.
+    #[must_use]
+    pub fn client_ip_header(mut self, name: &str) -> Self {
+        self.options.client_ip_header =
+            Some(HeaderName::try_from(name).expect("invalid client IP header name"));
+        self
+    }
+
+    /// Reads a deadline for each request from the `name` header, a Unix timestamp in
+    /// seconds such as `X-Request-Deadline: 1700000000.5`, and answers with
+    /// `504 Gateway Timeout` if it passes before the response is ready.
+    ///
+    /// Requests whose deadline has already passed get the `504` without being served. A
+    /// deadline passing while the body is being sent aborts the response instead, as its
+    /// status is already on its way. Requests without a valid timestamp have no deadline.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the header carrying the deadline.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` is not a valid header name.
+    #[must_use]
+    pub fn deadline_header(mut self, name: &str) -> Self {
+        self.options.deadline_header =
+            Some(HeaderName::try_from(name).expect("invalid deadline header name"));
+        self
+    }
+
+    /// Lets pages on any origin read the served files, with
+    /// `Access-Control-Allow-Origin: *` on every response.
+    ///
+    /// CORS preflight requests, `OPTIONS` requests carrying `Origin` and
+    /// `Access-Control-Request-Method`, are answered with `204 No Content` listing the
+    /// [allowed methods](Self::cors_allow_methods) and
+    /// [request headers](Self::cors_allow_headers).
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether cross-origin requests are allowed.
+    #[must_use]
+    pub fn cors(mut self, enabled: bool) -> Self {
+        self.options.cors = enabled;
+        self
+    }
+
+    /// Sets the methods listed in `Access-Control-Allow-Methods` on [CORS](Self::cors)
+    /// preflight responses. Defaults to `GET`, `HEAD` and `OPTIONS`.
+    ///
+    /// # Arguments
+    ///
+    /// * `methods` - The methods cross-origin requests may use.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a method is not a valid HTTP method token.
+    #[must_use]
+    pub fn cors_allow_methods<I, S>(mut self, methods: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.options.cors_allow_methods = methods
+            .into_iter()
+            .map(|method| Method::try_from(method.as_ref()).expect("invalid CORS method"))
+            .collect();
+        self
+    }
+
+    /// Sets the request headers listed in `Access-Control-Allow-Headers` on
+    /// [CORS](Self::cors) preflight responses, such as `Range` or `X-Requested-With`.
+    /// Defaults to none beyond the CORS-safelisted headers.
+    ///
+    /// # Arguments
+    ///
+    /// * `names` - The request headers cross-origin requests may send.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a name is not a valid header name.
+    #[must_use]
+    pub fn cors_allow_headers<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.options.cors_allow_headers = names
+            .into_iter()
+            .map(|name| HeaderName::try_from(name.as_ref()).expect("invalid CORS header name"))
+            .collect();
+        self
+    }
+
+    /// Sets `Access-Control-Max-Age` on [CORS](Self::cors) preflight responses, letting
+    /// browsers cache a preflight instead of repeating it before every request.
+    ///
+    /// The duration is sent as whole seconds. Browsers cap it, at two hours in Chromium.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_age` - How long browsers may cache a preflight response.
+    #[must_use]
+    pub fn cors_max_age(mut self, max_age: Duration) -> Self {
+        self.options.cors_max_age = Some(HeaderValue::from(max_age.as_secs()));
+        self
+    }
+
+    /// Shares one disk read between concurrent requests for the same file, so a burst
+    /// of requests for a file costs at most two reads.
+    ///
+    /// A request for a file no other request is being answered with streams it from
+    /// disk as usual. Requests arriving while one is in flight share a single read of
+    /// the whole file and are answered with the same bytes; once none are in flight,
+    /// the next request starts afresh. Files over 16 MiB, conditional
+    /// and range requests, and requests that may be answered with a precompressed
+    /// variant are streamed from disk per request as usual.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether concurrent reads are shared.
+    #[must_use]
+    pub fn coalesce_requests(mut self, enabled: bool) -> Self {
+        self.options.coalesce_requests = enabled.then(Flights::default);
+        self
+    }
+
+    /// Keeps small files in memory, revalidating them against the filesystem on every
+    /// request. Size limits can be set per extension, for example to cache stylesheets
+    /// and scripts but never images.
+    ///
+    /// Conditional and range requests, and requests that may be answered with a
+    /// precompressed variant, are still served from disk.
+    ///
+    /// # Arguments
+    ///
+    /// * `cache` - The capacity and per-extension limits of the cache.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// use axum_static::{MemoryCache, StaticRouter};
+    ///
+    /// let cache = MemoryCache::new(16 * 1024 * 1024)
+    ///     .extension_limit("css", 512 * 1024)
+    ///     .extension_limit("png", 0);
+    /// let app = StaticRouter::new("static/").memory_cache(cache).build();
+    /// ```
+    #[must_use]
+    pub fn memory_cache(mut self, cache: MemoryCache) -> Self {
+        self.options.memory_cache = Some(cache::Store::new(cache));
+        self
+    }
+
+    /// Builds the configured router.
+    ///
+    /// # Features
+    ///
+    /// When the `handle_error` feature is enabled, IO errors are handled by returning
+    /// a 500 Internal Server Error response. Errors from a dropped connection, such as
+    /// `BrokenPipe`, aren't routed there; see [`on_disconnect`](Self::on_disconnect).
+    pub fn build(self) -> Router {
+        let files = Files::new(&self.options);
+        let serve_dir = get(move |request: Request<Body>| files.clone().serve(request));
+
+        #[cfg(feature = "compression")]
+        let compression = self.options.compression.clone();
+
+        let router = Router::new()
+            .fallback_service(serve_dir)
+            .layer(from_fn_with_state(
+                Arc::new(self.options),
+                static_middleware,
+            ));
+
+        #[cfg(feature = "compression")]
+        let router = compression.apply(router);
+
+        router
+    }
+}
@@ -0,0 +1,198 @@
+//! In-memory caching of small, frequently served files.
+
+use axum::body::Bytes;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::SystemTime;
+
+use crate::fs;
+
+/// Limits of the in-memory file cache.
+///
+/// Files are cached whole, up to a size limit that can be set per extension, and
+/// revalidated against their size and modification time on every request, so
+/// replaced files are never served stale. The least recently used files are evicted
+/// once the total size would exceed the capacity.
+///
+/// ## Example
+///
+/// ```rust
+/// use axum_static::{MemoryCache, StaticRouter};
+///
+/// // Cache stylesheets and scripts up to 1 MiB each, but never images.
+/// let cache = MemoryCache::new(64 * 1024 * 1024)
+///     .extension_limit("css", 1024 * 1024)
+///     .extension_limit("js", 1024 * 1024)
+///     .extension_limit("png", 0)
+///     .extension_limit("jpg", 0);
+/// let app = StaticRouter::new("static/").memory_cache(cache).build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct MemoryCache {
+    capacity: u64,
+    max_file_size: u64,
+    extension_limits: HashMap<String, u64>,
+}
+
+impl MemoryCache {
+    /// Creates a cache holding up to `capacity` bytes, caching files of up to 64 KiB.
+    #[must_use]
+    pub fn new(capacity: u64) -> Self {
+        Self {
+            capacity,
+            max_file_size: 64 * 1024,
+            extension_limits: HashMap::new(),
+        }
+    }
+
+    /// Sets the size limit of files whose extension has no limit of its own.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The largest file size cached, or `0` to cache no such files.
+    #[must_use]
+    pub fn max_file_size(mut self, bytes: u64) -> Self {
+        self.max_file_size = bytes;
+        self
+    }
+
+    /// Sets the size limit of files with the given extension.
+    ///
+    /// # Arguments
+    ///
+    /// * `extension` - The file extension without the leading dot, such as `css`.
+    /// * `bytes` - The largest file size cached, or `0` to never cache these files.
+    #[must_use]
+    pub fn extension_limit(mut self, extension: &str, bytes: u64) -> Self {
+        self.extension_limits
+            .insert(extension.to_ascii_lowercase(), bytes);
+        self
+    }
+
+    /// Returns the largest size cached for files with the lowercased `extension`.
+    fn limit(&self, extension: Option<&str>) -> u64 {
+        extension
+            .and_then(|extension| self.extension_limits.get(extension))
+            .copied()
+            .unwrap_or(self.max_file_size)
+            .min(self.capacity)
+    }
+}
+
+/// A cached file with the metadata it was read with.
+#[derive(Debug)]
+struct Entry {
+    len: u64,
+    modified: SystemTime,
+    body: Bytes,
+    last_used: u64,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    files: HashMap<String, Entry>,
+    size: u64,
+    clock: u64,
+}
+
+/// The cache shared by the requests of a router.
+#[derive(Debug, Clone)]
+pub(crate) struct Store {
+    limits: MemoryCache,
+    entries: Arc<Mutex<Entries>>,
+}
+
+/// A file served from memory.
+#[derive(Debug, Clone)]
+pub(crate) struct Cached {
+    pub(crate) body: Bytes,
+    pub(crate) modified: SystemTime,
+}
+
+impl Store {
+    pub(crate) fn new(limits: MemoryCache) -> Self {
+        Self {
+            limits,
+            entries: Arc::default(),
+        }
+    }
+
+    /// Returns the contents of the file at the request path, reading it into the
+    /// cache on a miss, or `None` if the file is missing or not cacheable.
+    pub(crate) async fn get(
+        &self,
+        root: &Path,
+        request_path: &str,
+        extension: Option<&str>,
+    ) -> Option<Cached> {
+        let limit = self.limits.limit(extension);
+        if limit == 0 {
+            return None;
+        }
+
+        let path = fs::resolve(root, request_path)?;
+        let metadata = tokio::fs::metadata(&path).await.ok()?;
+        let modified = metadata.modified().ok()?;
+        if !metadata.is_file() || metadata.len() > limit {
+            return None;
+        }
+
+        if let Some(body) = self.hit(request_path, metadata.len(), modified) {
+            return Some(Cached { body, modified });
+        }
+
+        let body = Bytes::from(tokio::fs::read(&path).await.ok()?);
+        if body.len() as u64 == metadata.len() {
+            self.insert(request_path, modified, body.clone());
+        }
+        Some(Cached { body, modified })
+    }
+
+    /// Returns the cached body when it was read from a file of the same size and age.
+    fn hit(&self, request_path: &str, len: u64, modified: SystemTime) -> Option<Bytes> {
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        entries.clock += 1;
+        let clock = entries.clock;
+        let entry = entries.files.get_mut(request_path)?;
+        if entry.len != len || entry.modified != modified {
+            return None;
+        }
+        entry.last_used = clock;
+        Some(entry.body.clone())
+    }
+
+    /// Caches `body`, evicting the least recently used files to stay within capacity.
+    fn insert(&self, request_path: &str, modified: SystemTime, body: Bytes) {
+        let len = body.len() as u64;
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(stale) = entries.files.remove(request_path) {
+            entries.size -= stale.len;
+        }
+        while entries.size + len > self.limits.capacity {
+            let Some(oldest) = entries
+                .files
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(path, _)| path.clone())
+            else {
+                break;
+            };
+            if let Some(evicted) = entries.files.remove(&oldest) {
+                entries.size -= evicted.len;
+            }
+        }
+
+        entries.size += len;
+        let last_used = entries.clock;
+        entries.files.insert(
+            request_path.to_owned(),
+            Entry {
+                len,
+                modified,
+                body,
+                last_used,
+            },
+        );
+    }
+}
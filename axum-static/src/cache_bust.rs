@@ -0,0 +1,179 @@
+//! Versioning of local asset references in served HTML.
+
+use axum::{
+    body::Body,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::SystemTime;
+
+use crate::buffer::{self, Buffered, MAX_SIZE};
+use crate::fs;
+
+/// Attributes whose values are rewritten.
+const ATTRIBUTES: [&str; 2] = ["src", "href"];
+
+/// The size, modification time and version hash of each referenced file.
+type Hashes = HashMap<PathBuf, (u64, SystemTime, String)>;
+
+/// Version hashes of referenced files, remembered until the file changes.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Versions {
+    hashes: Arc<Mutex<Hashes>>,
+}
+
+impl Versions {
+    /// Appends a `v=<hash>` query parameter to local `src` and `href` references in
+    /// the HTML body, hashing the contents of each referenced file.
+    ///
+    /// `request_path` is the path the page was requested at, which relative
+    /// references are resolved against. External URLs, fragments and references to
+    /// missing files are left as-is. The page's validators are dropped, since it
+    /// changes whenever a referenced file does.
+    pub(crate) async fn apply(
+        &self,
+        root: &Path,
+        request_path: &str,
+        response: Response,
+    ) -> Response {
+        let (mut parts, body) = response.into_parts();
+        let body = match buffer::read(body).await {
+            Ok(Buffered::Complete(body)) => body,
+            Ok(Buffered::Oversized(body)) => return Response::from_parts(parts, body),
+            Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        };
+        let directory = request_path
+            .rsplit_once('/')
+            .map_or("", |(directory, _)| directory);
+
+        let mut versions = HashMap::new();
+        let mut rewritten = Vec::with_capacity(body.len());
+        let mut rest = &body[..];
+        while let Some((start, end)) = next_reference(rest) {
+            let reference = &rest[start..end];
+            let local = std::str::from_utf8(reference)
+                .ok()
+                .and_then(|reference| Some((reference, local_path(directory, reference)?)));
+            let version = match local {
+                Some((reference, path)) => {
+                    if !versions.contains_key(&path) {
+                        let hash = self.hash(root, &path).await;
+                        versions.insert(path.clone(), hash);
+                    }
+                    versions[&path]
+                        .as_deref()
+                        .map(|hash| versioned(reference, hash))
+                }
+                None => None,
+            };
+            rewritten.extend_from_slice(&rest[..start]);
+            match version {
+                Some(reference) => rewritten.extend_from_slice(reference.as_bytes()),
+                None => rewritten.extend_from_slice(reference),
+            }
+            rest = &rest[end..];
+        }
+        rewritten.extend_from_slice(rest);
+
+        let headers = &mut parts.headers;
+        buffer::drop_validators(headers);
+        Response::from_parts(parts, Body::from(rewritten))
+    }
+
+    /// Returns the first 8 bytes of the file's SHA-256 in hex, or `None` if it can't
+    /// be read or is over [`MAX_SIZE`].
+    ///
+    /// The file is only read again once its size or modification time changes.
+    async fn hash(&self, root: &Path, request_path: &str) -> Option<String> {
+        let path = fs::resolve(root, request_path)?;
+        let metadata = tokio::fs::metadata(&path).await.ok()?;
+        if !metadata.is_file() || metadata.len() > MAX_SIZE as u64 {
+            return None;
+        }
+
+        let modified = metadata.modified().ok()?;
+        if let Some((length, time, hash)) = self.lock().get(&path)
+            && *length == metadata.len()
+            && *time == modified
+        {
+            return Some(hash.clone());
+        }
+
+        let contents = tokio::fs::read(&path).await.ok()?;
+        let mut hex = String::with_capacity(16);
+        for byte in &Sha256::digest(&contents)[..8] {
+            let _ = write!(hex, "{byte:02x}");
+        }
+        self.lock()
+            .insert(path, (metadata.len(), modified, hex.clone()));
+        Some(hex)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Hashes> {
+        self.hashes.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Returns the reference with `v=<hash>` appended to its query, before any fragment.
+fn versioned(reference: &str, hash: &str) -> String {
+    let (before_fragment, fragment) = reference
+        .find('#')
+        .map_or((reference, ""), |index| reference.split_at(index));
+    let separator = if before_fragment.contains('?') {
+        '&'
+    } else {
+        '?'
+    };
+    format!("{before_fragment}{separator}v={hash}{fragment}")
+}
+
+/// Returns the byte range of the next quoted `src` or `href` attribute value in `html`.
+fn next_reference(html: &[u8]) -> Option<(usize, usize)> {
+    let mut offset = 0;
+    while let Some(found) = html[offset..].iter().position(|&byte| byte == b'=') {
+        let equals = offset + found;
+        offset = equals + 1;
+
+        let name = html[..equals].trim_ascii_end();
+        let Some(attribute) = ATTRIBUTES.iter().find(|attribute| {
+            name.len() > attribute.len()
+                && name[name.len() - attribute.len()..].eq_ignore_ascii_case(attribute.as_bytes())
+        }) else {
+            continue;
+        };
+        // The attribute name must stand alone, not end a longer name like `data-src`.
+        if !html[name.len() - attribute.len() - 1].is_ascii_whitespace() {
+            continue;
+        }
+
+        let value = html[offset..].trim_ascii_start();
+        let start = html.len() - value.len();
+        let Some(&quote @ (b'"' | b'\'')) = html.get(start) else {
+            continue;
+        };
+        let end = start + 1 + html[start + 1..].iter().position(|&byte| byte == quote)?;
+        return Some((start + 1, end));
+    }
+    None
+}
+
+/// Returns the request path of a local reference, resolved against `directory`.
+///
+/// References with a scheme, protocol-relative and fragment-only references, and
+/// references climbing above the root are not local.
+fn local_path(directory: &str, reference: &str) -> Option<String> {
+    let path = reference
+        .split(['?', '#'])
+        .next()
+        .filter(|path| !path.is_empty())?;
+    if path.starts_with("//") || path.split('/').next()?.contains(':') {
+        return None;
+    }
+
+    fs::join(directory, path)
+}
@@ -0,0 +1,105 @@
+//! Proxying requests for missing files to an upstream origin.
+
+use axum::{
+    body::Body,
+    http::{HeaderMap, Method, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use percent_encoding::{AsciiSet, CONTROLS, utf8_percent_encode};
+use std::time::Duration;
+
+use crate::source;
+
+/// How long connecting to the upstream may take.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long the upstream may go without sending data before the fetch is abandoned.
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Characters percent-encoded in forwarded path segments.
+const SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?');
+
+/// Upstream response headers passed through to the client.
+const FORWARDED_HEADERS: [header::HeaderName; 7] = [
+    header::CONTENT_TYPE,
+    header::CONTENT_LENGTH,
+    header::CONTENT_ENCODING,
+    header::CACHE_CONTROL,
+    header::ETAG,
+    header::LAST_MODIFIED,
+    header::EXPIRES,
+];
+
+/// The origin missing files are fetched from.
+#[derive(Debug, Clone)]
+pub(crate) struct Upstream {
+    /// The base URL without a trailing `/`, such as `https://cdn.example.com/assets`.
+    base: String,
+    client: reqwest::Client,
+}
+
+impl Upstream {
+    /// Creates an upstream fetching from `base`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the TLS backend cannot be initialized, as `reqwest::Client::new` does.
+    pub(crate) fn new(base: &str) -> Self {
+        let client = reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .read_timeout(READ_TIMEOUT)
+            .build()
+            .expect("initialize HTTP client");
+        Self {
+            base: base.trim_end_matches('/').to_owned(),
+            client,
+        }
+    }
+
+    /// Fetches `path_and_query` from the upstream and streams its response back.
+    ///
+    /// Failures to reach the upstream, including timeouts, are answered with
+    /// `502 Bad Gateway`.
+    pub(crate) async fn fetch(&self, method: &Method, path_and_query: &str) -> Response {
+        let url = format!("{}{path_and_query}", self.base);
+        let Ok(upstream) = self.client.request(method.clone(), url).send().await else {
+            return StatusCode::BAD_GATEWAY.into_response();
+        };
+
+        let mut headers = HeaderMap::new();
+        for name in FORWARDED_HEADERS {
+            if let Some(value) = upstream.headers().get(&name) {
+                headers.insert(name, value.clone());
+            }
+        }
+        let status = upstream.status();
+
+        let mut response = Response::new(Body::from_stream(upstream.bytes_stream()));
+        *response.status_mut() = status;
+        *response.headers_mut() = headers;
+        response
+    }
+}
+
+/// Returns the path forwarded upstream for `request_path`: its segments as resolved
+/// against the root, re-encoded, or `None` if it leaves the root.
+pub(crate) fn forwarded_path(request_path: &str) -> Option<String> {
+    let source = source::source_path(request_path)?;
+    let mut path = String::new();
+    for segment in source.split('/').filter(|segment| !segment.is_empty()) {
+        path.push('/');
+        path.extend(utf8_percent_encode(segment, SEGMENT));
+    }
+    if path.is_empty() || request_path.ends_with('/') {
+        path.push('/');
+    }
+    Some(path)
+}
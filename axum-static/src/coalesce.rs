@@ -0,0 +1,95 @@
+//! Single-flight reads shared by concurrent requests for the same file.
+
+use axum::body::Bytes;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use tokio::sync::OnceCell;
+
+use crate::buffer::MAX_SIZE;
+use crate::cache::Cached;
+use crate::fs;
+
+/// A read of one file, shared by the requests that join another in flight.
+type Read = Arc<OnceCell<Option<Cached>>>;
+
+/// The requests in flight for one file and the read they share.
+#[derive(Debug, Default)]
+struct Flight {
+    requests: usize,
+    read: Read,
+}
+
+/// The requests in flight, keyed by request path.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Flights {
+    pending: Arc<Mutex<HashMap<String, Flight>>>,
+}
+
+/// A request counted in flight for a file, until it is dropped.
+pub(crate) struct Seat {
+    request_path: String,
+    /// The shared read, for requests joining another already in flight.
+    read: Option<Read>,
+    pending: Arc<Mutex<HashMap<String, Flight>>>,
+}
+
+impl Flights {
+    /// Counts a request for the file at the request path as in flight until the
+    /// returned seat is dropped.
+    pub(crate) fn board(&self, request_path: &str) -> Seat {
+        let mut pending = lock(&self.pending);
+        let flight = pending.entry(request_path.to_owned()).or_default();
+        flight.requests += 1;
+        Seat {
+            request_path: request_path.to_owned(),
+            read: (flight.requests > 1).then(|| Arc::clone(&flight.read)),
+            pending: Arc::clone(&self.pending),
+        }
+    }
+}
+
+impl Seat {
+    /// Returns the contents of the file, read once for all the requests that joined
+    /// another in flight.
+    ///
+    /// Returns `None` for a request that found none in flight, which streams the file
+    /// from disk instead, and for missing files and files over [`MAX_SIZE`].
+    pub(crate) async fn read(&self, root: &Path) -> Option<Cached> {
+        self.read
+            .as_ref()?
+            .get_or_init(|| read(root, &self.request_path))
+            .await
+            .clone()
+    }
+}
+
+impl Drop for Seat {
+    fn drop(&mut self) {
+        // Once no request is in flight, the next one reads the file afresh.
+        let mut pending = lock(&self.pending);
+        if let Some(flight) = pending.get_mut(&self.request_path) {
+            flight.requests -= 1;
+            if flight.requests == 0 {
+                pending.remove(&self.request_path);
+            }
+        }
+    }
+}
+
+fn lock(pending: &Mutex<HashMap<String, Flight>>) -> MutexGuard<'_, HashMap<String, Flight>> {
+    pending.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Reads the whole file at the request path, if it is small enough to share.
+async fn read(root: &Path, request_path: &str) -> Option<Cached> {
+    let path = fs::resolve(root, request_path)?;
+    let metadata = tokio::fs::metadata(&path).await.ok()?;
+    if !metadata.is_file() || metadata.len() > MAX_SIZE as u64 {
+        return None;
+    }
+
+    let modified = metadata.modified().ok()?;
+    let body = Bytes::from(tokio::fs::read(path).await.ok()?);
+    Some(Cached { body, modified })
+}
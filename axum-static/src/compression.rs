@@ -0,0 +1,171 @@
+//! On-the-fly response compression.
+
+use axum::{
+    Router,
+    body::Bytes,
+    http::{Extensions, HeaderMap, HeaderValue, StatusCode, Version, header},
+    response::Response,
+};
+use http_body::{Frame, SizeHint};
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tower_http::compression::{
+    CompressionLayer,
+    predicate::{DefaultPredicate, Predicate},
+};
+
+use crate::encoding;
+
+/// Which content codings are applied on the fly.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Settings {
+    /// Whether every compressible response is gzip-compressed on the fly.
+    pub(crate) gzip: bool,
+    /// Extensions gzip-compressed on the fly.
+    pub(crate) gzip_extensions: HashSet<String>,
+    /// Whether every compressible response is brotli-compressed on the fly.
+    pub(crate) brotli: bool,
+}
+
+/// Response extension selecting the codings a response may be compressed with.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Compress {
+    gzip: bool,
+    br: bool,
+}
+
+impl Compress {
+    /// Returns whether the client will receive this response compressed.
+    pub(crate) fn applies(self, request_headers: &HeaderMap) -> bool {
+        self.coding(request_headers).is_some()
+    }
+
+    /// Returns the coding the client will receive this response in, if compressed.
+    ///
+    /// The brotli layer runs first, so it wins whenever the client accepts brotli.
+    fn coding(self, request_headers: &HeaderMap) -> Option<&'static str> {
+        if self.br && encoding::accepts(request_headers, "br") {
+            Some("br")
+        } else if self.gzip && encoding::accepts(request_headers, "gzip") {
+            Some("gzip")
+        } else {
+            None
+        }
+    }
+}
+
+/// Gives the response to a `HEAD` the headers the compression layers give the `GET`.
+///
+/// The layers judge a response by the size of its body, so they leave the empty body
+/// of a `HEAD` uncompressed, and its headers would describe a different representation
+/// than the `GET`'s. The response is marked as encoded here instead, which the layers
+/// then pass through.
+pub(crate) fn mirror_head(response: &mut Response, request_headers: &HeaderMap) {
+    let Some(coding) = response
+        .extensions()
+        .get::<Compress>()
+        .and_then(|marker| marker.coding(request_headers))
+    else {
+        return;
+    };
+
+    let headers = response.headers();
+    if headers.contains_key(header::CONTENT_ENCODING) || headers.contains_key(header::CONTENT_RANGE)
+    {
+        return;
+    }
+    let mut probe = Response::new(UnknownSize);
+    *probe.headers_mut() = headers.clone();
+    if !DefaultPredicate::new().should_compress(&probe) {
+        return;
+    }
+
+    let headers = response.headers_mut();
+    if !headers
+        .get_all(header::VARY)
+        .iter()
+        .any(|value| value.as_bytes().eq_ignore_ascii_case(b"accept-encoding"))
+    {
+        headers.append(header::VARY, HeaderValue::from_static("accept-encoding"));
+    }
+    headers.remove(header::ACCEPT_RANGES);
+    headers.remove(header::CONTENT_LENGTH);
+    headers.insert(header::CONTENT_ENCODING, HeaderValue::from_static(coding));
+}
+
+/// An empty body of unknown size, which makes the compression predicate judge a
+/// response by its `Content-Length` as it would the streamed file.
+struct UnknownSize;
+
+impl http_body::Body for UnknownSize {
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        Poll::Ready(None)
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        SizeHint::new()
+    }
+}
+
+impl Settings {
+    /// Returns the marker for a response to a file with the given extension.
+    pub(crate) fn marker(&self, extension: Option<&str>) -> Option<Compress> {
+        let gzip = self.gzip
+            || extension.is_some_and(|extension| self.gzip_extensions.contains(extension));
+        let br = self.brotli;
+        (gzip || br).then_some(Compress { gzip, br })
+    }
+
+    /// Wraps the router in the compression layers for the enabled codings.
+    ///
+    /// Brotli runs innermost so that clients accepting both codings receive brotli;
+    /// the gzip layer skips responses that already carry a `Content-Encoding`.
+    pub(crate) fn apply(&self, router: Router) -> Router {
+        let router = if self.brotli {
+            router.layer(
+                CompressionLayer::new()
+                    .no_gzip()
+                    .no_deflate()
+                    .no_zstd()
+                    .compress_when(DefaultPredicate::new().and(marked_br as MarkedPredicate)),
+            )
+        } else {
+            router
+        };
+
+        if !self.gzip && self.gzip_extensions.is_empty() {
+            router
+        } else {
+            router.layer(
+                CompressionLayer::new()
+                    .no_br()
+                    .no_deflate()
+                    .no_zstd()
+                    .compress_when(DefaultPredicate::new().and(marked_gzip as MarkedPredicate)),
+            )
+        }
+    }
+}
+
+/// Predicate deciding whether a response is compressed.
+type MarkedPredicate = fn(StatusCode, Version, &HeaderMap, &Extensions) -> bool;
+
+/// Compression predicate accepting responses marked for gzip.
+fn marked_gzip(_: StatusCode, _: Version, _: &HeaderMap, extensions: &Extensions) -> bool {
+    extensions
+        .get::<Compress>()
+        .is_some_and(|marker| marker.gzip)
+}
+
+/// Compression predicate accepting responses marked for brotli.
+fn marked_br(_: StatusCode, _: Version, _: &HeaderMap, extensions: &Extensions) -> bool {
+    extensions.get::<Compress>().is_some_and(|marker| marker.br)
+}
@@ -0,0 +1,61 @@
+//! Cross-origin resource sharing for served files.
+
+use axum::{
+    body::Body,
+    http::{HeaderMap, HeaderName, HeaderValue, Method, Request, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+
+use crate::options::Options;
+
+/// Returns whether the request is a CORS preflight.
+pub(crate) fn is_preflight(request: &Request<Body>) -> bool {
+    request.method() == Method::OPTIONS
+        && request.headers().contains_key(header::ORIGIN)
+        && request
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_REQUEST_METHOD)
+}
+
+/// Returns the `204 No Content` answering a preflight with the configured methods,
+/// request headers and max age.
+pub(crate) fn preflight(options: &Options) -> Response {
+    let mut response = StatusCode::NO_CONTENT.into_response();
+    let headers = response.headers_mut();
+    allow_origin(headers);
+
+    let methods = options
+        .cors_allow_methods
+        .iter()
+        .map(Method::as_str)
+        .collect::<Vec<_>>()
+        .join(", ");
+    if let Ok(methods) = HeaderValue::try_from(methods) {
+        headers.insert(header::ACCESS_CONTROL_ALLOW_METHODS, methods);
+    }
+
+    if !options.cors_allow_headers.is_empty() {
+        let names = options
+            .cors_allow_headers
+            .iter()
+            .map(HeaderName::as_str)
+            .collect::<Vec<_>>()
+            .join(", ");
+        if let Ok(names) = HeaderValue::try_from(names) {
+            headers.insert(header::ACCESS_CONTROL_ALLOW_HEADERS, names);
+        }
+    }
+
+    if let Some(max_age) = &options.cors_max_age {
+        headers.insert(header::ACCESS_CONTROL_MAX_AGE, max_age.clone());
+    }
+    response
+}
+
+/// Allows any origin to read the response.
+pub(crate) fn allow_origin(headers: &mut HeaderMap) {
+    headers.insert(
+        header::ACCESS_CONTROL_ALLOW_ORIGIN,
+        HeaderValue::from_static("*"),
+    );
+}
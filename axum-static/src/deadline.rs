@@ -0,0 +1,72 @@
+//! Deadlines set by clients for serving their requests.
+
+use axum::{
+    body::{Body, Bytes},
+    http::{HeaderMap, HeaderName},
+};
+use http_body::{Frame, SizeHint};
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::time::{Instant, Sleep};
+
+/// Returns the time left until the deadline in the `name` header, a Unix timestamp in
+/// seconds such as `1700000000` or `1700000000.250`, or `None` without a valid one.
+///
+/// A deadline that has already passed leaves [`Duration::ZERO`].
+pub(crate) fn remaining(headers: &HeaderMap, name: &HeaderName) -> Option<Duration> {
+    let seconds = headers
+        .get(name)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<f64>()
+        .ok()?;
+    let deadline = UNIX_EPOCH.checked_add(Duration::try_from_secs_f64(seconds).ok()?)?;
+    Some(
+        deadline
+            .duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+/// A response body that fails once its deadline passes, aborting the response.
+pub(crate) struct Bounded {
+    inner: Body,
+    deadline: Pin<Box<Sleep>>,
+}
+
+impl Bounded {
+    pub(crate) fn new(inner: Body, deadline: Instant) -> Self {
+        Self {
+            inner,
+            deadline: Box::pin(tokio::time::sleep_until(deadline)),
+        }
+    }
+}
+
+impl http_body::Body for Bounded {
+    type Data = Bytes;
+    type Error = axum::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        if !self.inner.is_end_stream() && self.deadline.as_mut().poll(cx).is_ready() {
+            let err = io::Error::new(io::ErrorKind::TimedOut, "request deadline passed");
+            return Poll::Ready(Some(Err(axum::Error::new(err))));
+        }
+        Pin::new(&mut self.inner).poll_frame(cx)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
@@ -0,0 +1,78 @@
+//! Serving decompressed bytes from precompressed-only files.
+
+use async_compression::tokio::bufread::{BrotliDecoder, GzipDecoder};
+use axum::{
+    body::Body,
+    http::{HeaderMap, HeaderValue, Method, header},
+    response::Response,
+};
+use std::path::Path;
+use tokio::{fs::File, io::BufReader};
+use tokio_util::io::ReaderStream;
+
+use crate::{encoding, fs};
+
+/// Precompressed variants that may be decompressed, in order of preference.
+const VARIANTS: [(&str, &str); 2] = [("br", "br"), ("gz", "gzip")];
+
+/// Serves the decompressed contents of `<file>.br` or `<file>.gz` when `<file>` is missing.
+///
+/// Variants that `ServeDir` would serve directly, because precompressed serving is
+/// enabled for the coding and the client accepts it, are skipped. The body is decoded
+/// as it streams. Returns `None` when the requested file exists or has no variant.
+pub(crate) async fn serve(
+    root: &Path,
+    method: &Method,
+    request_path: &str,
+    request_headers: &HeaderMap,
+    served_precompressed: impl Fn(&str) -> bool,
+) -> Option<Response> {
+    if !matches!(*method, Method::GET | Method::HEAD) {
+        return None;
+    }
+
+    let path = fs::resolve(root, request_path)?;
+    if tokio::fs::metadata(&path).await.is_ok() {
+        return None;
+    }
+
+    for (suffix, coding) in VARIANTS {
+        if served_precompressed(coding) && encoding::accepts(request_headers, coding) {
+            continue;
+        }
+
+        let mut variant = path.clone().into_os_string();
+        variant.push(".");
+        variant.push(suffix);
+        let Ok(file) = File::open(&variant).await else {
+            continue;
+        };
+        if !file
+            .metadata()
+            .await
+            .is_ok_and(|metadata| metadata.is_file())
+        {
+            continue;
+        }
+
+        let body = if method == Method::HEAD {
+            Body::empty()
+        } else {
+            let reader = BufReader::new(file);
+            match coding {
+                "br" => Body::from_stream(ReaderStream::new(BrotliDecoder::new(reader))),
+                _ => Body::from_stream(ReaderStream::new(GzipDecoder::new(reader))),
+            }
+        };
+
+        let mut response = Response::new(body);
+        vary_accept_encoding(response.headers_mut());
+        return Some(response);
+    }
+    None
+}
+
+/// Marks the response as varying with the client's `Accept-Encoding`.
+fn vary_accept_encoding(headers: &mut HeaderMap) {
+    headers.append(header::VARY, HeaderValue::from_static("accept-encoding"));
+}
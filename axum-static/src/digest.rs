@@ -0,0 +1,67 @@
+//! `Digest` headers (RFC 3230) computed from served files.
+
+use axum::http::{HeaderName, HeaderValue};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use sha2::{Digest, Sha256, Sha512};
+use std::path::Path;
+#[cfg(feature = "tracing")]
+use tracing::warn;
+
+use crate::buffer::MAX_SIZE;
+use crate::fs;
+
+pub(crate) const DIGEST: HeaderName = HeaderName::from_static("digest");
+
+/// The hash algorithm of the `Digest` header.
+///
+/// ## Example
+///
+/// ```rust
+/// use axum_static::{DigestAlgorithm, StaticRouter};
+///
+/// let app = StaticRouter::new("static/")
+///     .digest_header(DigestAlgorithm::Sha256)
+///     .build();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DigestAlgorithm {
+    /// SHA-256, emitted as `sha-256=<base64>`.
+    Sha256,
+    /// SHA-512, emitted as `sha-512=<base64>`.
+    Sha512,
+}
+
+/// Returns the `Digest` header value for the file at the request path.
+///
+/// Files over [`MAX_SIZE`] are skipped rather than read into memory.
+pub(crate) async fn compute(
+    root: &Path,
+    request_path: &str,
+    algorithm: DigestAlgorithm,
+) -> Option<HeaderValue> {
+    let path = fs::resolve(root, request_path)?;
+    let metadata = tokio::fs::metadata(&path).await.ok()?;
+    if !metadata.is_file() {
+        return None;
+    }
+    if metadata.len() > MAX_SIZE as u64 {
+        #[cfg(feature = "tracing")]
+        warn!(
+            path = %request_path,
+            size = metadata.len(),
+            "File exceeds the digest size limit; omitting Digest header"
+        );
+        return None;
+    }
+
+    let contents = tokio::fs::read(path).await.ok()?;
+    let value = match algorithm {
+        DigestAlgorithm::Sha256 => {
+            format!("sha-256={}", STANDARD.encode(Sha256::digest(&contents)))
+        }
+        DigestAlgorithm::Sha512 => {
+            format!("sha-512={}", STANDARD.encode(Sha512::digest(&contents)))
+        }
+    };
+    HeaderValue::try_from(value).ok()
+}
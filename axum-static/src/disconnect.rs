@@ -0,0 +1,98 @@
+//! Detection of clients disconnecting before a response body is fully sent.
+
+use axum::body::{Body, Bytes};
+use axum::http::StatusCode;
+use http_body::{Frame, SizeHint};
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::options::{Callback, OnDisconnect};
+
+/// Returns whether the IO error means the connection went away, such as a broken pipe,
+/// rather than that the file couldn't be served.
+pub(crate) fn is_disconnect(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::BrokenPipe
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+    )
+}
+
+/// Returns the status of responses to requests whose connection went away, nginx's
+/// `499 Client Closed Request`. The client never sees it, but logs and metrics do.
+pub(crate) fn client_closed() -> StatusCode {
+    StatusCode::from_u16(499).expect("499 is a valid status code")
+}
+
+/// A response body reporting how much of it was sent if dropped before its end.
+pub(crate) struct Tracked {
+    inner: Body,
+    path: String,
+    sent: u64,
+    /// The exact body length, when known up front.
+    expected: Option<u64>,
+    finished: bool,
+    callback: Callback<OnDisconnect>,
+}
+
+impl Tracked {
+    /// Wraps `inner`, the body served for `path`.
+    pub(crate) fn new(inner: Body, path: String, callback: Callback<OnDisconnect>) -> Self {
+        Self {
+            expected: http_body::Body::size_hint(&inner).exact(),
+            inner,
+            path,
+            sent: 0,
+            finished: false,
+            callback,
+        }
+    }
+
+    /// Returns whether the whole body was handed to the connection.
+    fn complete(&self) -> bool {
+        self.finished
+            || self.expected == Some(self.sent)
+            || http_body::Body::is_end_stream(&self.inner)
+    }
+}
+
+impl http_body::Body for Tracked {
+    type Data = Bytes;
+    type Error = axum::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let poll = Pin::new(&mut self.inner).poll_frame(cx);
+        match &poll {
+            Poll::Ready(Some(Ok(frame))) => {
+                if let Some(data) = frame.data_ref() {
+                    self.sent += data.len() as u64;
+                }
+            }
+            // A failed read ends the body without the client going away.
+            Poll::Ready(Some(Err(_)) | None) => self.finished = true,
+            Poll::Pending => {}
+        }
+        poll
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        if !self.complete() {
+            (self.callback.0)(&self.path, self.sent);
+        }
+    }
+}
@@ -0,0 +1,94 @@
+//! `Accept-Encoding` negotiation.
+
+use axum::http::{HeaderMap, header};
+
+/// Precompressed variants served in place of a file, as `<file>.br`, `<file>.gz` or
+/// `<file>.zst` next to it.
+///
+/// ## Example
+///
+/// ```rust
+/// use axum_static::{Encodings, static_router_precompressed};
+///
+/// let app = static_router_precompressed("static/", Encodings::new().zstd().br().gzip());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Encodings {
+    pub(crate) br: bool,
+    pub(crate) gzip: bool,
+    pub(crate) zstd: bool,
+}
+
+impl Encodings {
+    /// Creates a set with no encodings.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds brotli, served from `<file>.br`.
+    #[must_use]
+    pub fn br(mut self) -> Self {
+        self.br = true;
+        self
+    }
+
+    /// Adds gzip, served from `<file>.gz`.
+    #[must_use]
+    pub fn gzip(mut self) -> Self {
+        self.gzip = true;
+        self
+    }
+
+    /// Adds zstd, served from `<file>.zst`.
+    #[must_use]
+    pub fn zstd(mut self) -> Self {
+        self.zstd = true;
+        self
+    }
+}
+
+/// Returns the quality value the client assigned to `coding`, if it mentioned it.
+///
+/// An explicit entry for `coding` takes precedence over the `*` wildcard. Entries
+/// without a `q` parameter have quality `1`.
+fn quality(headers: &HeaderMap, coding: &str) -> Option<f32> {
+    let mut wildcard = None;
+    for value in headers.get_all(header::ACCEPT_ENCODING) {
+        let Ok(value) = value.to_str() else {
+            continue;
+        };
+        for entry in value.split(',') {
+            let mut params = entry.split(';').map(str::trim);
+            let name = params.next().unwrap_or_default();
+            let q = params
+                .find_map(|param| param.strip_prefix("q=").or(param.strip_prefix("Q=")))
+                .map_or(Some(1.0), |q| q.parse::<f32>().ok());
+            let Some(q) = q else {
+                continue;
+            };
+
+            if name.eq_ignore_ascii_case(coding) {
+                return Some(q);
+            }
+            if name == "*" {
+                wildcard = Some(q);
+            }
+        }
+    }
+    wildcard
+}
+
+/// Returns whether the client accepts responses with the given content coding.
+#[cfg(feature = "compression")]
+pub(crate) fn accepts(headers: &HeaderMap, coding: &str) -> bool {
+    quality(headers, coding).is_some_and(|q| q > 0.0)
+}
+
+/// Returns whether the client accepts uncompressed responses.
+///
+/// Identity is acceptable unless it is refused explicitly with `identity;q=0`, or
+/// implicitly with `*;q=0` and no `identity` entry.
+pub(crate) fn accepts_identity(headers: &HeaderMap) -> bool {
+    quality(headers, "identity").is_none_or(|q| q > 0.0)
+}
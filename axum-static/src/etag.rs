@@ -0,0 +1,208 @@
+//! Entity tags and the preconditions evaluated against them.
+
+use axum::{
+    http::{HeaderMap, HeaderName, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::buffer::MAX_SIZE;
+use crate::{fs, source};
+
+/// The size, modification time and entity tag of each hashed file.
+type Tags = HashMap<PathBuf, (u64, SystemTime, String)>;
+
+/// Entity tags derived from file contents, remembered until the file changes.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ContentTags {
+    tags: Arc<Mutex<Tags>>,
+}
+
+impl ContentTags {
+    /// Returns the weak entity tag of the file at the request path, such as
+    /// `W/"5d41402abc4b2a76b9719d911017c592"`, from the SHA-256 of its contents.
+    ///
+    /// Files with the same contents get the same tag whenever they were written, and
+    /// the tag starts with `prefix` inside the quotes. Files over [`MAX_SIZE`] aren't
+    /// hashed and get the tag from [`compute`] instead.
+    pub(crate) async fn compute(
+        &self,
+        root: &Path,
+        request_path: &str,
+        prefix: &str,
+    ) -> Option<String> {
+        let path = fs::resolve(root, request_path)?;
+        let metadata = tokio::fs::metadata(&path).await.ok()?;
+        if !metadata.is_file() {
+            return None;
+        }
+        if metadata.len() > MAX_SIZE as u64 {
+            return compute(root, request_path, prefix).await;
+        }
+
+        let modified = metadata.modified().ok()?;
+        if let Some((length, time, tag)) = self.lock().get(&path)
+            && *length == metadata.len()
+            && *time == modified
+        {
+            return Some(tag.clone());
+        }
+
+        let contents = tokio::fs::read(&path).await.ok()?;
+        let mut tag = format!("W/\"{prefix}");
+        for byte in &Sha256::digest(&contents)[..16] {
+            let _ = write!(tag, "{byte:02x}");
+        }
+        tag.push('"');
+        self.lock()
+            .insert(path, (metadata.len(), modified, tag.clone()));
+        Some(tag)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Tags> {
+        self.tags.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Entity tags precomputed by a build tool, keyed by file path relative to the root.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Manifest(HashMap<String, String>);
+
+impl Manifest {
+    /// Records the tag of the file at `path`, such as `assets/app.js`, from `hash`,
+    /// which is quoted unless it already is a quoted or weak tag. Returns `false` if
+    /// the tag isn't valid.
+    pub(crate) fn insert(&mut self, path: &str, hash: &str) -> bool {
+        let tag = if hash.starts_with('"') || hash.starts_with("W/\"") {
+            hash.to_owned()
+        } else {
+            format!("\"{hash}\"")
+        };
+        let opaque = tag.trim_start_matches("W/");
+        let valid = opaque.len() >= 2
+            && opaque.starts_with('"')
+            && opaque.ends_with('"')
+            && is_valid_prefix(&opaque[1..opaque.len() - 1]);
+        if valid {
+            self.0.insert(path.trim_start_matches('/').to_owned(), tag);
+        }
+        valid
+    }
+
+    /// Returns the listed tag of the file at the request path, or `None` if it isn't
+    /// listed or isn't a file.
+    pub(crate) async fn get(&self, root: &Path, request_path: &str) -> Option<String> {
+        let tag = self.0.get(&source::source_path(request_path)?)?;
+        fs::is_file(root, request_path).await.then(|| tag.clone())
+    }
+}
+
+/// Returns the entity tag of the file at the request path, such as `"3e8-17f0a2b3c4d5e6f7"`.
+///
+/// The tag is derived from the file's size and modification time, so it changes whenever
+/// the file is replaced, and starts with `prefix` inside the quotes. Directories and
+/// missing files have no tag.
+pub(crate) async fn compute(root: &Path, request_path: &str, prefix: &str) -> Option<String> {
+    let path = fs::resolve(root, request_path)?;
+    let metadata = tokio::fs::metadata(path).await.ok()?;
+    if !metadata.is_file() {
+        return None;
+    }
+
+    let modified = metadata
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_nanos();
+    Some(format!("\"{prefix}{:x}-{modified:x}\"", metadata.len()))
+}
+
+/// Returns whether `prefix` may appear inside a quoted entity tag.
+pub(crate) fn is_valid_prefix(prefix: &str) -> bool {
+    prefix
+        .bytes()
+        .all(|byte| byte == 0x21 || (0x23..=0x7e).contains(&byte))
+}
+
+/// Returns the tag in its weak form, for responses whose bytes differ from the file's.
+pub(crate) fn weak(etag: &str) -> String {
+    if etag.starts_with("W/") {
+        etag.to_owned()
+    } else {
+        format!("W/{etag}")
+    }
+}
+
+/// Returns the response called for by the request's preconditions on `etag`, if any.
+///
+/// A non-matching `If-Match` fails with `412 Precondition Failed` when `if_match` is
+/// enabled, using the strong comparison; a matching `If-None-Match` answers
+/// `304 Not Modified`, using the weak comparison.
+pub(crate) fn precondition(
+    request_headers: &HeaderMap,
+    etag: &str,
+    if_match: bool,
+) -> Option<Response> {
+    if if_match
+        && request_headers.contains_key(header::IF_MATCH)
+        && !matches(request_headers, &header::IF_MATCH, etag, true)
+    {
+        return Some(StatusCode::PRECONDITION_FAILED.into_response());
+    }
+
+    if matches(request_headers, &header::IF_NONE_MATCH, etag, false) {
+        return Some(StatusCode::NOT_MODIFIED.into_response());
+    }
+
+    None
+}
+
+/// Returns whether the request's `If-Range` names an entity tag other than the current
+/// `etag`, so the whole file must be sent instead of the requested range.
+///
+/// Weak tags can't validate a range, so a weak `If-Range`, or a weak current tag,
+/// always fails, as does any tag when entity tags are disabled. Dates are left to
+/// `ServeDir`.
+pub(crate) fn if_range_fails(request_headers: &HeaderMap, etag: Option<&str>) -> bool {
+    let Some(if_range) = request_headers
+        .get(header::IF_RANGE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+    else {
+        return false;
+    };
+    if if_range.starts_with("W/") {
+        return true;
+    }
+    if_range.starts_with('"') && etag.is_none_or(|etag| etag.starts_with("W/") || etag != if_range)
+}
+
+/// Returns whether any entity tag listed in the `name` headers matches `etag`.
+///
+/// The strong comparison never matches a weak tag; the weak comparison ignores the
+/// `W/` marker on either side. A `*` matches any tag.
+fn matches(request_headers: &HeaderMap, name: &HeaderName, etag: &str, strong: bool) -> bool {
+    let etag = etag.trim_start_matches("W/");
+    request_headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|candidate| {
+            if candidate == "*" {
+                return true;
+            }
+            match candidate.strip_prefix("W/") {
+                Some(_) if strong => false,
+                Some(candidate) => candidate == etag,
+                None => candidate == etag,
+            }
+        })
+}
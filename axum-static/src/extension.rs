@@ -0,0 +1,41 @@
+//! Content types of compound file extensions such as `.tar.gz`.
+
+/// Compound extensions with a content type of their own, and that type.
+const COMPOUND: [(&str, &str); 3] = [
+    ("tar.gz", "application/x-gtar"),
+    ("tar.bz2", "application/x-gtar"),
+    ("tar.xz", "application/x-gtar"),
+];
+
+/// How the extension used for content-type inference is chosen when a file name has
+/// several, as in `file.min.js` or `archive.tar.gz`.
+///
+/// ## Example
+///
+/// ```rust
+/// use axum_static::{ExtensionStrategy, StaticRouter};
+///
+/// // Serve `archive.tar.gz` as `application/x-gtar` rather than `application/gzip`.
+/// let app = StaticRouter::new("static/")
+///     .compound_extension_strategy(ExtensionStrategy::MostSpecific)
+///     .build();
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExtensionStrategy {
+    /// Uses the last extension, so `archive.tar.gz` is inferred from `gz`.
+    #[default]
+    LastSegment,
+    /// Uses a known compound extension, such as `tar.gz`, when the file name ends with
+    /// one, and the last extension otherwise. `file.min.js` is still inferred from `js`.
+    MostSpecific,
+}
+
+/// Returns the content type of the compound extension ending the request path, if any.
+pub(crate) fn compound_content_type(path: &str) -> Option<&'static str> {
+    let name = path.rsplit('/').next()?.to_ascii_lowercase();
+    COMPOUND.iter().find_map(|(extension, content_type)| {
+        name.strip_suffix(extension)
+            .is_some_and(|stem| stem.len() > 1 && stem.ends_with('.'))
+            .then_some(*content_type)
+    })
+}
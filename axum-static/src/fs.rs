@@ -0,0 +1,113 @@
+//! Mapping request paths onto the served directory.
+
+use axum::http::HeaderValue;
+use percent_encoding::percent_decode_str;
+use std::path::{Component, Path, PathBuf};
+
+/// Resolves a request path to a filesystem path under `root`.
+///
+/// The path is percent-decoded and rejected if any component could escape `root`,
+/// mirroring the validation `ServeDir` applies before serving.
+pub(crate) fn resolve(root: &Path, request_path: &str) -> Option<PathBuf> {
+    let decoded = percent_decode_str(request_path.trim_start_matches('/'))
+        .decode_utf8()
+        .ok()?;
+
+    let mut path = root.to_path_buf();
+    for component in Path::new(&*decoded).components() {
+        match component {
+            Component::Normal(segment)
+                if Path::new(segment)
+                    .components()
+                    .all(|inner| matches!(inner, Component::Normal(_))) =>
+            {
+                path.push(segment);
+            }
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    Some(path)
+}
+
+/// Joins a relative reference onto the request path of `directory`, resolving `.` and
+/// `..` segments. Absolute references replace the directory.
+///
+/// Returns `None` for references climbing above the root.
+pub(crate) fn join(directory: &str, reference: &str) -> Option<String> {
+    let joined = if reference.starts_with('/') {
+        reference.to_owned()
+    } else {
+        format!("{directory}/{reference}")
+    };
+    let mut segments = Vec::new();
+    for segment in joined.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop()?;
+            }
+            segment => segments.push(segment),
+        }
+    }
+    Some(format!("/{}", segments.join("/")))
+}
+
+/// Returns whether the request path resolves to a regular file under `root`.
+pub(crate) async fn is_file(root: &Path, request_path: &str) -> bool {
+    let Some(path) = resolve(root, request_path) else {
+        return false;
+    };
+    tokio::fs::metadata(path)
+        .await
+        .is_ok_and(|metadata| metadata.is_file())
+}
+
+/// Returns whether the request path resolves to a directory under `root`.
+pub(crate) async fn is_dir(root: &Path, request_path: &str) -> bool {
+    let Some(path) = resolve(root, request_path) else {
+        return false;
+    };
+    tokio::fs::metadata(path)
+        .await
+        .is_ok_and(|metadata| metadata.is_dir())
+}
+
+/// Returns whether the request path resolves to an existing file or directory under `root`.
+pub(crate) async fn exists(root: &Path, request_path: &str) -> bool {
+    match resolve(root, request_path) {
+        Some(path) => tokio::fs::try_exists(path).await.unwrap_or(false),
+        None => false,
+    }
+}
+
+/// Returns whether the request path resolves to something other than a regular file or
+/// directory, such as a named pipe or device file.
+pub(crate) async fn is_special(root: &Path, request_path: &str) -> bool {
+    let Some(path) = resolve(root, request_path) else {
+        return false;
+    };
+    tokio::fs::metadata(path)
+        .await
+        .is_ok_and(|metadata| !metadata.is_file() && !metadata.is_dir())
+}
+
+/// Returns the media type stored in the `<file>.meta` sidecar of the request path.
+///
+/// The sidecar holds the media type on its first line, such as `application/x-custom`.
+/// Sidecars over a kilobyte are ignored, as no media type is that long.
+pub(crate) async fn sidecar_mime(root: &Path, request_path: &str) -> Option<HeaderValue> {
+    let mut path = resolve(root, request_path)?.into_os_string();
+    path.push(".meta");
+    let metadata = tokio::fs::metadata(&path).await.ok()?;
+    if !metadata.is_file() || metadata.len() > 1024 {
+        return None;
+    }
+
+    let contents = tokio::fs::read_to_string(path).await.ok()?;
+    let mime = contents.lines().next()?.trim();
+    if mime.is_empty() {
+        return None;
+    }
+    HeaderValue::from_str(mime).ok()
+}
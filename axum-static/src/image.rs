@@ -0,0 +1,48 @@
+//! `Accept` negotiation between image formats of a file.
+
+use axum::http::{HeaderMap, header};
+
+/// Extensions of images that may have modern-format siblings.
+const NEGOTIABLE: [&str; 4] = ["jpg", "jpeg", "png", "gif"];
+
+/// Modern formats tried in order of preference, with their media types.
+const FORMATS: [(&str, &str); 2] = [("avif", "image/avif"), ("webp", "image/webp")];
+
+/// Returns whether files with the lowercased `extension` are negotiated.
+pub(crate) fn is_negotiable(extension: Option<&str>) -> bool {
+    extension.is_some_and(|extension| NEGOTIABLE.contains(&extension))
+}
+
+/// Returns the extensions of the formats to try for a request, most preferred first.
+///
+/// Only formats the `Accept` header names explicitly with a non-zero quality count;
+/// browsers send `image/*` or `*/*` whether or not they can decode a given format.
+pub(crate) fn candidates(headers: &HeaderMap) -> Vec<&'static str> {
+    let accepted = headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|entry| {
+            let mut params = entry.split(';').map(str::trim);
+            let media_type = params.next()?.to_ascii_lowercase();
+            let q = params
+                .find_map(|param| param.strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.parse::<f32>().ok())?;
+            (q > 0.0).then_some(media_type)
+        })
+        .collect::<Vec<_>>();
+
+    FORMATS
+        .iter()
+        .filter(|(_, media_type)| accepted.iter().any(|accepted| accepted == media_type))
+        .map(|(extension, _)| *extension)
+        .collect()
+}
+
+/// Returns the request path of the sibling of `path` with the given extension, so
+/// `/img/photo.jpg` becomes `/img/photo.avif`.
+pub(crate) fn sibling_path(path: &str, extension: &str) -> Option<String> {
+    let (stem, _) = path.rsplit_once('.')?;
+    Some(format!("{stem}.{extension}"))
+}
@@ -0,0 +1,112 @@
+//! Server-side includes assembling HTML pages from fragment files.
+
+use axum::{
+    body::Body,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+
+use crate::buffer::{self, Buffered};
+use crate::fs;
+
+/// Nesting depth at which includes stop being expanded.
+const MAX_DEPTH: usize = 16;
+
+/// What replaces a directive that can't be processed, as in Apache's `mod_include`.
+const ERROR: &str = "[an error occurred while processing this directive]";
+
+/// Replaces `<!--#include file="..."-->` and `<!--#include virtual="..."-->`
+/// directives in the HTML body with the contents of the named files, recursively.
+///
+/// Paths are relative to the including file, though `virtual` paths may also start
+/// with `/` to name a file from the root. Directives naming files outside the root,
+/// missing files, or a file already being included are replaced with an error
+/// message. The page's validators are dropped, since it changes whenever a fragment
+/// does.
+pub(crate) async fn apply(root: &Path, served_path: &str, response: Response) -> Response {
+    let (mut parts, body) = response.into_parts();
+    let body = match buffer::read(body).await {
+        Ok(Buffered::Complete(body)) => body,
+        Ok(Buffered::Oversized(body)) => return Response::from_parts(parts, body),
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    let mut including = vec![served_path.to_owned()];
+    let expanded = expand(root, &body, &mut including).await;
+
+    let headers = &mut parts.headers;
+    buffer::drop_validators(headers);
+    Response::from_parts(parts, Body::from(expanded))
+}
+
+/// Expands the directives in `html`, the contents of the last file in `including`.
+///
+/// Bytes outside directives are copied as they are, whatever the page's encoding.
+fn expand<'a>(
+    root: &'a Path,
+    html: &'a [u8],
+    including: &'a mut Vec<String>,
+) -> Pin<Box<dyn Future<Output = Vec<u8>> + Send + 'a>> {
+    Box::pin(async move {
+        let mut expanded = Vec::with_capacity(html.len());
+        let mut rest = html;
+        while let Some(start) = buffer::find(rest, b"<!--#include") {
+            let Some(length) = buffer::find(&rest[start..], b"-->") else {
+                break;
+            };
+            let end = start + length + b"-->".len();
+            expanded.extend_from_slice(&rest[..start]);
+
+            let directive = std::str::from_utf8(&rest[start..end]).ok();
+            match directive {
+                Some(directive) => match fragment(root, directive, including).await {
+                    Some(contents) => expanded.extend_from_slice(&contents),
+                    None => expanded.extend_from_slice(ERROR.as_bytes()),
+                },
+                None => expanded.extend_from_slice(ERROR.as_bytes()),
+            }
+            rest = &rest[end..];
+        }
+        expanded.extend_from_slice(rest);
+        expanded
+    })
+}
+
+/// Returns the expanded contents of the file named by an include `directive`.
+async fn fragment(root: &Path, directive: &str, including: &mut Vec<String>) -> Option<Vec<u8>> {
+    let current = including.last()?;
+    let directory = current
+        .rsplit_once('/')
+        .map_or("", |(directory, _)| directory);
+    let path = match (
+        attribute(directive, "file"),
+        attribute(directive, "virtual"),
+    ) {
+        (Some(file), _) if !file.starts_with('/') => fs::join(directory, file)?,
+        (None, Some(path)) => fs::join(directory, path)?,
+        _ => return None,
+    };
+    if including.len() >= MAX_DEPTH || including.contains(&path) {
+        return None;
+    }
+
+    let contents = tokio::fs::read(fs::resolve(root, &path)?).await.ok()?;
+    including.push(path);
+    let expanded = expand(root, &contents, including).await;
+    including.pop();
+    Some(expanded)
+}
+
+/// Returns the quoted value of the `name` attribute of a directive.
+fn attribute<'a>(directive: &'a str, name: &str) -> Option<&'a str> {
+    let start = directive.find(&format!(" {name}="))? + name.len() + 2;
+    let value = directive.get(start..)?;
+    let quote = value
+        .chars()
+        .next()
+        .filter(|quote| matches!(quote, '"' | '\''))?;
+    let value = &value[1..];
+    Some(&value[..value.find(quote)?])
+}
@@ -0,0 +1,44 @@
+//! Random offsets spreading out the expiry of cached responses.
+
+use axum::http::HeaderValue;
+
+/// Returns `Cache-Control` with its `max-age` moved by a random offset of up to
+/// `jitter` seconds either way, or `None` if it has no `max-age`.
+///
+/// The offset never takes `max-age` below zero.
+pub(crate) fn apply(cache_control: &HeaderValue, jitter: u64) -> Option<HeaderValue> {
+    let value = cache_control.to_str().ok()?;
+    let mut found = false;
+    let directives = value
+        .split(',')
+        .map(|directive| {
+            let trimmed = directive.trim();
+            match trimmed
+                .get(..8)
+                .filter(|name| name.eq_ignore_ascii_case("max-age="))
+                .and_then(|_| trimmed[8..].parse::<u64>().ok())
+            {
+                Some(max_age) if !found => {
+                    found = true;
+                    format!("max-age={}", offset(max_age, jitter))
+                }
+                _ => trimmed.to_owned(),
+            }
+        })
+        .collect::<Vec<_>>();
+    if !found {
+        return None;
+    }
+    HeaderValue::try_from(directives.join(", ")).ok()
+}
+
+/// Returns `max_age` plus a uniformly random offset in `-jitter..=jitter`.
+fn offset(max_age: u64, jitter: u64) -> u64 {
+    let mut bytes = [0; 8];
+    if getrandom::fill(&mut bytes).is_err() {
+        return max_age;
+    }
+    let spread = jitter.saturating_mul(2).saturating_add(1);
+    let step = u64::from_ne_bytes(bytes) % spread;
+    max_age.saturating_add(step).saturating_sub(jitter)
+}
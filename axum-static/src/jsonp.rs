@@ -0,0 +1,67 @@
+//! JSONP wrapping of JSON responses for legacy cross-domain clients.
+
+use axum::{
+    body::Body,
+    http::{HeaderValue, Method, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+
+use crate::buffer::{self, Buffered};
+
+/// Returns the value of the `callback` query parameter, if present.
+pub(crate) fn callback(query: Option<&str>) -> Option<&str> {
+    query?
+        .split('&')
+        .find_map(|param| param.strip_prefix("callback="))
+}
+
+/// Returns whether `callback` is a JavaScript identifier or a dotted path of them,
+/// such as `handle` or `app.handle`, so it can't inject script into the response.
+pub(crate) fn is_valid(callback: &str) -> bool {
+    callback.split('.').all(|name| {
+        let mut chars = name.chars();
+        chars
+            .next()
+            .is_some_and(|first| first.is_ascii_alphabetic() || first == '_' || first == '$')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+    })
+}
+
+/// Wraps the JSON body as a call to `callback`, served as JavaScript.
+///
+/// The body starts with an empty comment, a common guard against content-sniffing
+/// attacks on JSONP endpoints. The validators of the JSON file are dropped. `HEAD`
+/// responses get the same headers and an empty body. JSON files over
+/// [`buffer::MAX_SIZE`] are served unwrapped.
+pub(crate) async fn wrap(response: Response, callback: &str, method: &Method) -> Response {
+    let (mut parts, body) = response.into_parts();
+    let json = match buffer::read(body).await {
+        Ok(Buffered::Complete(body)) => body,
+        Ok(Buffered::Oversized(body)) => return Response::from_parts(parts, body),
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+
+    let mut script = Vec::with_capacity(json.len() + callback.len() + 8);
+    script.extend_from_slice(b"/**/");
+    script.extend_from_slice(callback.as_bytes());
+    script.push(b'(');
+    script.extend_from_slice(&json);
+    script.extend_from_slice(b");");
+
+    let headers = &mut parts.headers;
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/javascript"),
+    );
+    headers.insert(
+        header::X_CONTENT_TYPE_OPTIONS,
+        HeaderValue::from_static("nosniff"),
+    );
+    buffer::drop_validators(headers);
+    let body = if method == Method::HEAD {
+        Body::empty()
+    } else {
+        Body::from(script)
+    };
+    Response::from_parts(parts, body)
+}
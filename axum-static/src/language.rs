@@ -0,0 +1,56 @@
+//! `Accept-Language` negotiation between language variants of a file.
+
+use axum::http::{HeaderMap, header};
+
+/// Returns the languages to try, most preferred first.
+///
+/// The client's `Accept-Language` preferences come first, ordered by quality, with
+/// each region-qualified tag followed by its primary subtag. The configured `chain`
+/// follows, and duplicates are dropped.
+pub(crate) fn candidates(headers: &HeaderMap, chain: &[String]) -> Vec<String> {
+    let mut preferences = headers
+        .get_all(header::ACCEPT_LANGUAGE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|entry| {
+            let mut params = entry.split(';').map(str::trim);
+            let tag = params.next()?.to_ascii_lowercase();
+            let q = params
+                .find_map(|param| param.strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.parse::<f32>().ok())?;
+            (!tag.is_empty() && tag != "*" && q > 0.0).then_some((tag, q))
+        })
+        .collect::<Vec<_>>();
+    preferences.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+    let mut languages: Vec<String> = Vec::new();
+    let mut push = |language: &str| {
+        if !languages.iter().any(|existing| existing == language) {
+            languages.push(language.to_owned());
+        }
+    };
+    for (tag, _) in &preferences {
+        push(tag);
+        if let Some((primary, _)) = tag.split_once('-') {
+            push(primary);
+        }
+    }
+    for language in chain {
+        push(&language.to_ascii_lowercase());
+    }
+    languages
+}
+
+/// Returns the request path of the `language` variant of `path`.
+///
+/// Variants insert the language before the extension, so `/docs/page.html` becomes
+/// `/docs/page.fr.html`. Paths whose file name has no extension have no variants.
+pub(crate) fn variant_path(path: &str, language: &str) -> Option<String> {
+    let (directory, file) = path.rsplit_once('/')?;
+    let (stem, extension) = file.rsplit_once('.')?;
+    if stem.is_empty() {
+        return None;
+    }
+    Some(format!("{directory}/{stem}.{language}.{extension}"))
+}
@@ -9,6 +9,9 @@
 //! - `handle_error`: Enables error handling for IO errors when serving files.
 //! - `mime_guess`: Uses the `mime_guess` crate for exhaustive MIME inference.
 //! - `status_code`: Enhances error responses with human-readable status messages.
+//! - `compression`: Enables on-the-fly gzip and brotli compression of selected responses.
+//! - `cdn_fallback`: Enables proxying requests for missing files to an upstream origin.
+//! - `unicode_normalize`: Enables normalizing request paths to a Unicode normalization form.
 //!
 //! ## Example
 //!
@@ -18,29 +21,73 @@
 //! let app = static_router("static/");
 //! ```
 
-#[cfg(feature = "handle_error")]
-use axum::http::StatusCode;
-#[cfg(feature = "handle_error")]
-use axum::response::IntoResponse;
 use axum::{
     Router,
     body::Body,
-    http::Request,
-    middleware::{Next, from_fn},
+    http::{Request, header},
+    middleware::Next,
     response::Response,
-    routing::get_service,
 };
-#[cfg(all(feature = "handle_error", feature = "status_code"))]
-use status_code::statuses;
-#[cfg(feature = "handle_error")]
-use std::io;
+use percent_encoding::percent_decode_str;
 use std::path::Path;
-use tower_http::services::ServeDir;
 
 #[cfg(feature = "tracing")]
-use tracing::{error, warn};
+use tracing::warn;
+
+mod buffer;
+mod builder;
+mod cache;
+mod cache_bust;
+#[cfg(feature = "cdn_fallback")]
+mod cdn;
+mod coalesce;
+#[cfg(feature = "compression")]
+mod compression;
+mod cors;
+mod deadline;
+#[cfg(feature = "compression")]
+mod decompress;
+mod digest;
+mod disconnect;
+mod encoding;
+mod etag;
+mod extension;
+mod fs;
+mod image;
+mod include;
+mod jitter;
+mod jsonp;
+mod language;
+mod limit;
+mod middleware;
+mod mime_types;
+mod network;
+mod nonce;
+#[cfg(feature = "unicode_normalize")]
+mod normalize;
+mod options;
+mod plaintext;
+mod policy;
+mod preload;
+mod redirect;
+pub mod resolver;
+mod serve;
+mod source;
+mod status;
+mod suggest;
+mod timing;
+
+pub use builder::StaticRouter;
+pub use cache::MemoryCache;
+pub use digest::DigestAlgorithm;
+pub use encoding::Encodings;
+pub use extension::ExtensionStrategy;
+#[cfg(feature = "unicode_normalize")]
+pub use normalize::NormalizationForm;
+pub use policy::CachePolicy;
+pub use preload::PreloadLink;
+pub use source::{AsyncFileSource, EmbeddedAssets, FileStat, SourceFuture};
 
-#[cfg(not(feature = "mime_guess"))]
 fn infer_content_type_from_extension(extension: &str) -> &'static str {
     match extension {
         "html" => "text/html",
@@ -89,6 +136,9 @@ fn infer_content_type_from_extension(extension: &str) -> &'static str {
         "m3u8" => "application/vnd.apple.mpegurl",
         "ts" => "video/mp2t",
         "webp" => "image/webp",
+        "avif" => "image/avif",
+        "heic" => "image/heic",
+        "jxl" => "image/jxl",
         "bmp" => "image/bmp",
         "tif" | "tiff" => "image/tiff",
         "psd" => "image/vnd.adobe.photoshop",
@@ -103,33 +153,43 @@ fn infer_content_type_from_extension(extension: &str) -> &'static str {
     }
 }
 
-/// Middleware that sets the `Content-Type` header based on the file extension.
-///
-/// This middleware inspects the request URI's path, extracts the file extension,
-/// and maps it to the appropriate MIME type. If no extension is found or it's unknown,
-/// it defaults to "application/octet-stream".
+/// Extracts the lowercased file extension used for content-type inference.
 ///
-/// Note: This does not override an existing `Content-Type` header.
-pub async fn content_type_middleware(request: Request<Body>, next: Next) -> Response {
-    let uri = request.uri().to_owned();
-    let path = uri.path();
-
-    // Extract the extension before awaiting to avoid holding a borrow across await points.
-    let extension = path.rsplit('.').next().map(str::to_ascii_lowercase);
+/// Only the last path segment is considered, so `/assets/v1.2/logo`, `/download` and
+/// `/docs/` have no extension. Query and fragment remnants left in the path, even
+/// percent-encoded ones, and trailing punctuation are ignored, so `app.js?v=1` and
+/// `style.css#x` still have the extensions `js` and `css`.
+pub(crate) fn path_extension(path: &str) -> Option<String> {
+    let name = percent_decode_str(path.rsplit('/').next()?).decode_utf8_lossy();
+    let name = name.split(['?', '#']).next()?;
+    let (_, extension) = name.rsplit_once('.')?;
+    let extension = extension.trim_end_matches(|c: char| !c.is_ascii_alphanumeric());
+    (!extension.is_empty()).then(|| extension.to_ascii_lowercase())
+}
 
-    let mut response = next.run(request).await;
+/// Infers the MIME type for a request path from its file extension.
+///
+/// Unknown extensions map to "application/octet-stream", and paths without an
+/// extension map to "unknown".
+pub(crate) fn infer_content_type(path: &str) -> &'static str {
+    let extension = path_extension(path);
 
     #[cfg(feature = "mime_guess")]
     let content_type = {
-        let guessed = mime_guess::from_path(path).first_raw();
-        match (guessed, extension.as_deref()) {
-            (Some(mime), _) => mime,
-            (None, Some(ext)) => {
+        // Look up the same lowercased extension as the built-in table, so both
+        // configurations treat `FILE.PDF` like `file.pdf`.
+        match extension.as_deref() {
+            Some(ext) => {
+                let mime = mime_guess::from_ext(ext)
+                    .first_raw()
+                    .unwrap_or("application/octet-stream");
                 #[cfg(feature = "tracing")]
-                warn!(%path, %ext, "Unknown MIME type; defaulting to application/octet-stream");
-                "application/octet-stream"
+                if mime == "application/octet-stream" {
+                    warn!(%path, %ext, "Unknown MIME type; defaulting to application/octet-stream");
+                }
+                mime
             }
-            (None, None) => "unknown",
+            None => "unknown",
         }
     };
 
@@ -146,11 +206,52 @@ pub async fn content_type_middleware(request: Request<Body>, next: Next) -> Resp
         None => "unknown",
     };
 
+    content_type
+}
+
+/// Middleware that sets the `Content-Type` header based on the file extension.
+///
+/// This middleware inspects the request URI's path, extracts the file extension,
+/// and maps it to the appropriate MIME type. If no extension is found or it's unknown,
+/// it defaults to "application/octet-stream".
+///
+/// Note: This does not override an existing `Content-Type` header.
+pub async fn content_type_middleware(request: Request<Body>, next: Next) -> Response {
+    let uri = request.uri().to_owned();
+
+    let mut response = next.run(request).await;
+    set_content_type(&mut response, uri.path());
+
+    response
+}
+
+/// Media type of responses holding several ranges of a file.
+const MULTIPART_BYTERANGES: &str = "multipart/byteranges";
+
+/// Sets the `Content-Type` header inferred from the request path on the response.
+///
+/// A `multipart/byteranges` response to a multi-range request is left alone, as its
+/// `Content-Type` carries the boundary separating the parts. `ServeDir` itself answers
+/// multi-range requests with `416 Range Not Satisfiable`, so this only matters for
+/// services that assemble multipart responses.
+pub(crate) fn set_content_type(response: &mut Response, path: &str) {
+    let multipart = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| {
+            value
+                .get(..MULTIPART_BYTERANGES.len())
+                .is_some_and(|prefix| prefix.eq_ignore_ascii_case(MULTIPART_BYTERANGES))
+        });
+    if multipart {
+        return;
+    }
+
+    let content_type = infer_content_type(path);
     if let Ok(content_type) = content_type.parse() {
         response.headers_mut().insert("Content-Type", content_type);
     }
-
-    response
 }
 
 /// Creates a router that serves static files from the given directory.
@@ -159,6 +260,9 @@ pub async fn content_type_middleware(request: Request<Body>, next: Next) -> Resp
 /// appended for directories. It applies the `content_type_middleware` to set appropriate
 /// content types.
 ///
+/// This is shorthand for `StaticRouter::new(path).build()`; use [`StaticRouter`] directly
+/// to configure additional behavior.
+///
 /// # Arguments
 ///
 /// * `path` - The path to the directory containing static files.
@@ -168,50 +272,173 @@ pub async fn content_type_middleware(request: Request<Body>, next: Next) -> Resp
 /// When the `handle_error` feature is enabled, IO errors are handled by returning
 /// a 500 Internal Server Error response.
 pub fn static_router<P: AsRef<Path>>(path: P) -> Router {
-    /// Error handler for IO errors when serving static files.
-    ///
-    /// This function returns a 500 Internal Server Error response with the error message.
-    ///
-    /// # Arguments
-    ///
-    /// * `err` - The IO error that occurred.
-    ///
-    /// # Features
-    ///
-    /// This function is only available when the `handle_error` feature is enabled.
-    #[cfg(feature = "handle_error")]
-    async fn handle_error(err: io::Error) -> impl IntoResponse {
-        #[cfg(feature = "status_code")]
-        let (status, body) = {
-            let status = StatusCode::INTERNAL_SERVER_ERROR;
-            let code = status.as_u16();
-            let label = statuses::code(code);
-            let body = format!("static router IO error ({} {}): {:?}", code, label, err);
-            (status, body)
-        };
-
-        #[cfg(not(feature = "status_code"))]
-        let (status, body) = {
-            let status = StatusCode::INTERNAL_SERVER_ERROR;
-            let body = format!("static router IO error: {:?}", err);
-            (status, body)
-        };
-
-        #[cfg(feature = "tracing")]
-        {
-            error!(%body, error = %err, "static router IO error");
-        }
+    StaticRouter::new(path).build()
+}
+
+/// Creates a router that serves static files from the given directory with caching
+/// headers.
+///
+/// Each file gets the `Cache-Control` chosen by `policy` for its type, unless the
+/// response already carries one, and an `ETag` so that clients can revalidate. This is
+/// shorthand for `StaticRouter::new(path).cache_policy(policy).etag(true).build()`.
+///
+/// # Arguments
+///
+/// * `path` - The path to the directory containing static files.
+/// * `policy` - The `Cache-Control` values by file type.
+///
+/// ## Example
+///
+/// ```rust
+/// use axum_static::{CachePolicy, static_router_cached};
+/// use std::time::Duration;
+///
+/// let app = static_router_cached("static/", CachePolicy::new(Duration::from_secs(600)));
+/// ```
+pub fn static_router_cached<P: AsRef<Path>>(path: P, policy: CachePolicy) -> Router {
+    StaticRouter::new(path)
+        .cache_policy(policy)
+        .etag(true)
+        .build()
+}
 
-        (status, body).into_response()
+/// Creates a router that serves precompressed variants of files to clients that
+/// accept them.
+///
+/// A request for `app.js` from a client accepting brotli is answered with `app.js.br`
+/// when it exists, with `Content-Encoding: br` and the `Content-Type` of `app.js`.
+/// Clients that accept none of the encodings, or files without a variant, get the
+/// file itself. This is shorthand for enabling [`StaticRouter::precompressed_br`],
+/// [`StaticRouter::precompressed_gzip`] and [`StaticRouter::precompressed_zstd`] as
+/// selected.
+///
+/// # Arguments
+///
+/// * `path` - The path to the directory containing static files.
+/// * `encodings` - The precompressed variants to look for.
+pub fn static_router_precompressed<P: AsRef<Path>>(path: P, encodings: Encodings) -> Router {
+    let mut router = StaticRouter::new(path);
+    if encodings.br {
+        router = router.precompressed_br();
     }
+    if encodings.gzip {
+        router = router.precompressed_gzip();
+    }
+    if encodings.zstd {
+        router = router.precompressed_zstd();
+    }
+    router.build()
+}
 
-    let serve_dir = ServeDir::new(path).append_index_html_on_directories(true);
-    #[cfg(feature = "handle_error")]
-    let serve_dir = get_service(serve_dir).handle_error(handle_error);
-    #[cfg(not(feature = "handle_error"))]
-    let serve_dir = get_service(serve_dir);
+/// Creates a router that serves a single-page app from the given directory.
+///
+/// Client-side routes such as `/dashboard/settings` that don't resolve to a file are
+/// answered with the root `index.html` and `200 OK`, labelled `text/html`, while real
+/// files like `/assets/app.js` are served normally. Missing paths with an extension,
+/// such as `/assets/missing.js`, are still a 404, as is every route if `index.html`
+/// itself is missing.
+///
+/// This is shorthand for `StaticRouter::new(path).spa_fallback(true).build()`.
+///
+/// # Arguments
+///
+/// * `path` - The path to the directory containing the app.
+///
+/// ## Example
+///
+/// ```rust
+/// use axum_static::spa_router;
+///
+/// let app = spa_router("dist/");
+/// ```
+pub fn spa_router<P: AsRef<Path>>(path: P) -> Router {
+    StaticRouter::new(path).spa_fallback(true).build()
+}
 
-    Router::new()
-        .fallback_service(serve_dir)
-        .layer(from_fn(content_type_middleware))
+/// Creates a router that serves static files from the given directory under the
+/// given path prefix.
+///
+/// `Router::nest` only carries a nested router's routes, not its fallback, so a
+/// static router nested that way stops answering once it's two levels deep, and
+/// never answers the prefix with a trailing slash. The router returned here mounts
+/// the files as a service, which answers `/static/` and everything below it, and can
+/// itself be nested or merged at any depth; `/static` is redirected to `/static/`, so
+/// relative links in the index resolve. Paths are resolved, and content types
+/// inferred, without the prefix, while redirects keep it.
+///
+/// # Arguments
+///
+/// * `prefix` - The path the files are mounted under, such as `/static`.
+/// * `path` - The path to the directory containing static files.
+///
+/// # Panics
+///
+/// Panics if `prefix` is `/` or doesn't start with `/`.
+///
+/// ## Example
+///
+/// ```rust
+/// use axum::Router;
+/// use axum_static::static_router_at;
+///
+/// // `/app/static/app.css` is served from `public/app.css`.
+/// let app = Router::new().nest("/app", static_router_at("/static", "public/"));
+/// ```
+pub fn static_router_at<P: AsRef<Path>>(prefix: &str, path: P) -> Router {
+    Router::new().nest_service(prefix, static_router(path))
+}
+
+/// Creates a router that serves static files from the given directory, with
+/// content types for extensions the built-in table and `mime_guess` don't know.
+///
+/// Overrides are consulted first, so they also replace built-in types. This is
+/// shorthand for calling [`StaticRouter::content_type_for_extension`] for each one.
+///
+/// # Arguments
+///
+/// * `path` - The path to the directory containing static files.
+/// * `overrides` - Pairs of extensions without the leading dot and MIME types, such as
+///   a `HashMap<String, String>` or `[("glb", "model/gltf-binary")]`.
+///
+/// # Panics
+///
+/// Panics if an override's MIME type is not a valid header value.
+///
+/// ## Example
+///
+/// ```rust
+/// use axum_static::static_router_with;
+///
+/// let app = static_router_with(
+///     "static/",
+///     [("glb", "model/gltf-binary"), ("myapp", "application/x-myapp")],
+/// );
+/// ```
+pub fn static_router_with<P, I, E, M>(path: P, overrides: I) -> Router
+where
+    P: AsRef<Path>,
+    I: IntoIterator<Item = (E, M)>,
+    E: AsRef<str>,
+    M: AsRef<str>,
+{
+    overrides
+        .into_iter()
+        .fold(StaticRouter::new(path), |router, (extension, mime)| {
+            router.content_type_for_extension(extension.as_ref(), mime.as_ref())
+        })
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::path_extension;
+
+    #[test]
+    fn extensions_come_from_the_last_segment() {
+        assert_eq!(path_extension("/assets/app.JS").as_deref(), Some("js"));
+        assert_eq!(path_extension("/archive.tar.gz").as_deref(), Some("gz"));
+        assert_eq!(path_extension("/assets/v1.2/logo"), None);
+        assert_eq!(path_extension("/download"), None);
+        assert_eq!(path_extension("/docs/"), None);
+    }
 }
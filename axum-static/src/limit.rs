@@ -0,0 +1,138 @@
+//! Limits on the requests each client may have in flight at once.
+
+use axum::{
+    body::{Body, Bytes},
+    extract::ConnectInfo,
+    http::{HeaderName, Request},
+};
+use http_body::{Frame, SizeHint};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{Context, Poll};
+
+/// Counts the requests in flight per client address.
+#[derive(Debug, Clone)]
+pub(crate) struct ConnectionLimit {
+    max: usize,
+    active: Arc<Mutex<HashMap<IpAddr, usize>>>,
+}
+
+/// Whether a request may proceed under the limit.
+pub(crate) enum Admission {
+    /// The client can't be identified, so isn't limited.
+    Unidentified,
+    /// The client took a slot, released once the response is done.
+    Admitted(Slot),
+    /// The client already has the maximum number of requests in flight.
+    Refused,
+}
+
+/// A request slot held by a client, released when dropped.
+pub(crate) struct Slot {
+    client: IpAddr,
+    active: Arc<Mutex<HashMap<IpAddr, usize>>>,
+}
+
+impl ConnectionLimit {
+    pub(crate) fn new(max: usize) -> Self {
+        Self {
+            max,
+            active: Arc::default(),
+        }
+    }
+
+    /// Takes a slot for the client of the request.
+    ///
+    /// Clients are identified by the last address in the `header`, as appended by the
+    /// proxy in front of the router, or otherwise by the peer address in the request's
+    /// [`ConnectInfo`].
+    pub(crate) fn admit<B>(&self, request: &Request<B>, header: Option<&HeaderName>) -> Admission {
+        let Some(client) = client(request, header) else {
+            return Admission::Unidentified;
+        };
+
+        let mut active = self.active.lock().unwrap_or_else(PoisonError::into_inner);
+        let count = active.entry(client).or_default();
+        if *count >= self.max {
+            return Admission::Refused;
+        }
+        *count += 1;
+        Admission::Admitted(Slot {
+            client,
+            active: Arc::clone(&self.active),
+        })
+    }
+}
+
+/// Returns the address of the client making the request, if known.
+///
+/// See [`ConnectionLimit::admit`] for how clients are identified.
+pub(crate) fn client<B>(request: &Request<B>, header: Option<&HeaderName>) -> Option<IpAddr> {
+    if let Some(header) = header {
+        return request
+            .headers()
+            .get_all(header)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .next_back()?
+            .trim()
+            .parse()
+            .ok();
+    }
+
+    request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(address)| address.ip())
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        let mut active = self.active.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(count) = active.get_mut(&self.client) {
+            *count -= 1;
+            if *count == 0 {
+                active.remove(&self.client);
+            }
+        }
+    }
+}
+
+/// A response body holding a guard, such as its client's slot, until it is sent or
+/// dropped.
+pub(crate) struct Held<T> {
+    inner: Body,
+    _guard: T,
+}
+
+impl<T> Held<T> {
+    pub(crate) fn new(inner: Body, guard: T) -> Self {
+        Self {
+            inner,
+            _guard: guard,
+        }
+    }
+}
+
+impl<T: Unpin> http_body::Body for Held<T> {
+    type Data = Bytes;
+    type Error = axum::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        Pin::new(&mut self.inner).poll_frame(cx)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
@@ -0,0 +1,929 @@
+//! Request-time behavior of the configured static router.
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{
+        HeaderMap, HeaderName, HeaderValue, Method, Request, StatusCode, Uri, header,
+        uri::PathAndQuery,
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+#[cfg(feature = "tracing")]
+use tracing::error;
+
+use crate::cache::Cached;
+#[cfg(feature = "cdn_fallback")]
+use crate::cdn;
+use crate::coalesce::Seat;
+#[cfg(feature = "compression")]
+use crate::compression::{self, Compress};
+use crate::deadline::{self, Bounded};
+#[cfg(feature = "compression")]
+use crate::decompress;
+use crate::digest::{self, DIGEST};
+use crate::disconnect::Tracked;
+use crate::extension::{self, ExtensionStrategy};
+use crate::limit::{self, Admission, Held};
+#[cfg(feature = "unicode_normalize")]
+use crate::normalize;
+use crate::options::{DefaultFile, Options};
+use crate::timing::ServerTiming;
+use crate::{
+    cors, encoding, etag, fs, image, include, jitter, jsonp, language, nonce, plaintext, redirect,
+    resolver, suggest,
+};
+use crate::{infer_content_type, path_extension, set_content_type};
+
+pub(crate) const ACCEPT_CH: HeaderName = HeaderName::from_static("accept-ch");
+pub(crate) const CROSS_ORIGIN_EMBEDDER_POLICY: HeaderName =
+    HeaderName::from_static("cross-origin-embedder-policy");
+pub(crate) const CROSS_ORIGIN_OPENER_POLICY: HeaderName =
+    HeaderName::from_static("cross-origin-opener-policy");
+/// The path prefix of ACME HTTP-01 challenges, followed by the token.
+const ACME_CHALLENGE: &str = "/.well-known/acme-challenge/";
+const CLEAR_SITE_DATA: HeaderName = HeaderName::from_static("clear-site-data");
+const SERVER_TIMING: HeaderName = HeaderName::from_static("server-timing");
+
+/// What was requested and what is being served in response.
+struct Exchange {
+    /// The request path before any rewrite.
+    requested_path: String,
+    /// The query string of the request.
+    query: Option<String>,
+    /// The request path after rewrites, which determines the served file.
+    served_path: String,
+    /// The lowercased extension of the served path.
+    extension: Option<String>,
+    /// The entity tag of the served file, when entity tags are enabled.
+    etag: Option<String>,
+    /// The request method.
+    method: Method,
+    /// The request headers, kept for decisions made after the request is consumed.
+    request_headers: HeaderMap,
+}
+
+/// Middleware applying the builder options around the served file.
+///
+/// Content types are inferred as in [`content_type_middleware`](crate::content_type_middleware),
+/// from the path of the file actually served.
+pub(crate) async fn static_middleware(
+    State(options): State<Arc<Options>>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let slot = match &options.connection_limit {
+        Some(limit) => match limit.admit(&request, options.client_ip_header.as_ref()) {
+            Admission::Admitted(slot) => Some(slot),
+            Admission::Unidentified => None,
+            Admission::Refused => return StatusCode::TOO_MANY_REQUESTS.into_response(),
+        },
+        None => None,
+    };
+
+    let remaining = options
+        .deadline_header
+        .as_ref()
+        .and_then(|name| deadline::remaining(request.headers(), name));
+    let exceeded = || (StatusCode::GATEWAY_TIMEOUT, "deadline exceeded").into_response();
+    let mut response = match remaining {
+        Some(Duration::ZERO) => exceeded(),
+        Some(remaining) => {
+            let deadline = tokio::time::Instant::now() + remaining;
+            match tokio::time::timeout_at(deadline, serve(&options, request, next)).await {
+                Ok(response) => response.map(|body| Body::new(Bounded::new(body, deadline))),
+                Err(_) => exceeded(),
+            }
+        }
+        None => serve(&options, request, next).await,
+    };
+
+    if response.status() == StatusCode::SERVICE_UNAVAILABLE
+        && let Some(delay) = &options.retry_after
+    {
+        response
+            .headers_mut()
+            .insert(header::RETRY_AFTER, delay.clone());
+    }
+
+    if options.cors {
+        cors::allow_origin(response.headers_mut());
+    }
+
+    for name in &options.strip_headers {
+        response.headers_mut().remove(name);
+    }
+
+    if let Some(slot) = slot {
+        response = response.map(|body| Body::new(Held::new(body, slot)));
+    }
+    response
+}
+
+/// Resolves, serves and decorates the response for a request.
+async fn serve(options: &Options, mut request: Request<Body>, next: Next) -> Response {
+    // Diagnostics and certificate renewal stay reachable while the site is in maintenance.
+    if let Some((path, status)) = &options.status_endpoint
+        && request.uri().path() == path
+    {
+        return generated_file(options, status, request.method());
+    }
+
+    if !options.acme_challenges.is_empty()
+        && let Some(token) = request.uri().path().strip_prefix(ACME_CHALLENGE)
+    {
+        return match options.acme_challenges.get(token) {
+            Some(key_authorization) => generated_file(options, key_authorization, request.method()),
+            None => StatusCode::NOT_FOUND.into_response(),
+        };
+    }
+
+    if options.maintenance && !allowlisted(options, &request) {
+        return (StatusCode::SERVICE_UNAVAILABLE, "service unavailable").into_response();
+    }
+
+    if options.cors && cors::is_preflight(&request) {
+        return cors::preflight(options);
+    }
+
+    #[cfg(feature = "unicode_normalize")]
+    if let Some(form) = options.unicode_normalize
+        && let Some(path) = normalize::path(request.uri().path(), form)
+    {
+        rewrite_path(&mut request, &path);
+    }
+
+    let target = path_redirect(options, request.uri()).await;
+    if let Some(redirect) = redirect_for(options, &request, target) {
+        return redirect;
+    }
+
+    if redirect::is_bare_prefix(&request)
+        || directory_redirect_applies(options, request.uri().path()).await
+    {
+        return redirect::directory(&request);
+    }
+
+    let mut timing = ServerTiming::default();
+    let requested_path = request.uri().path().to_owned();
+    let started = Instant::now();
+    rewrite_request(options, &mut request).await;
+    if options.stats_on_rewrite() {
+        timing.stat = Some(started.elapsed());
+    }
+
+    let served_path = request.uri().path().to_owned();
+    // Assembled, versioned and nonced pages differ from the page file, so its validators
+    // don't describe them.
+    let rewrites_html =
+        (options.server_side_includes || options.cache_bust_html.is_some() || options.csp_nonce)
+            && infer_content_type(&served_path).starts_with("text/html");
+    let etag = if options.etag && !rewrites_html && serves_representation(request.method()) {
+        entity_tag(options, &served_path).await
+    } else {
+        None
+    };
+    let exchange = Exchange {
+        requested_path,
+        query: request.uri().query().map(str::to_owned),
+        extension: path_extension(&served_path),
+        served_path,
+        etag,
+        method: request.method().clone(),
+        request_headers: request.headers().clone(),
+    };
+    strip_request_headers(options, &exchange, rewrites_html, &mut request);
+
+    // Held until the response is done, so requests arriving meanwhile share one read.
+    let seat = options
+        .coalesce_requests
+        .as_ref()
+        .filter(|_| serves_from_memory(options, &exchange, request.method()))
+        .map(|flights| flights.board(&exchange.served_path));
+    let generated = generated(options, &exchange, request.method(), seat.as_ref()).await;
+    let mut response = if let Some(response) = generated {
+        response
+    } else {
+        from_disk(options, &exchange, request, next, &mut timing).await
+    };
+    decorate(options, &exchange, &mut response).await;
+
+    response = rewrite_body(options, &exchange, response).await;
+    if let Some(seat) = seat {
+        response = response.map(|body| Body::new(Held::new(body, seat)));
+    }
+
+    #[cfg(feature = "compression")]
+    if exchange.method == Method::HEAD {
+        compression::mirror_head(&mut response, &exchange.request_headers);
+    }
+
+    if options.server_timing
+        && let Some(value) = timing.header_value()
+    {
+        response.headers_mut().insert(SERVER_TIMING, value);
+    }
+
+    if refuses_identity(&exchange.request_headers, &response) {
+        return StatusCode::NOT_ACCEPTABLE.into_response();
+    }
+
+    if let Some(callback) = &options.on_disconnect {
+        let path = exchange.served_path;
+        let callback = callback.clone();
+        response = response.map(|body| Body::new(Tracked::new(body, path, callback)));
+    }
+
+    response
+}
+
+/// Removes the request headers asking for responses that can't be served as they are.
+fn strip_request_headers(
+    options: &Options,
+    exchange: &Exchange,
+    rewrites_html: bool,
+    request: &mut Request<Body>,
+) {
+    // JSONP responses and rewritten pages replace the whole file, so ranges of it can't
+    // be served. `ServeDir` ignores `If-Range`, so a failed one is applied here.
+    if options.disable_ranges
+        || rewrites_html
+        || jsonp_callback(options, exchange).is_some()
+        || etag::if_range_fails(&exchange.request_headers, exchange.etag.as_deref())
+    {
+        request.headers_mut().remove(header::RANGE);
+        request.headers_mut().remove(header::IF_RANGE);
+    }
+    // Precompressed variants can't be rewritten, so rewritten pages are read from the
+    // page file itself; on-the-fly compression still applies afterwards.
+    if rewrites_html {
+        request.headers_mut().remove(header::IF_MODIFIED_SINCE);
+        request.headers_mut().remove(header::ACCEPT_ENCODING);
+    }
+}
+
+/// Rewrites the bodies of full, unencoded responses as the options call for.
+async fn rewrite_body(options: &Options, exchange: &Exchange, mut response: Response) -> Response {
+    if response.status() != StatusCode::OK
+        || response.headers().contains_key(header::CONTENT_ENCODING)
+    {
+        return response;
+    }
+
+    if let Some(callback) = jsonp_callback(options, exchange) {
+        response = jsonp::wrap(response, callback, &exchange.method).await;
+    }
+
+    if options.server_side_includes && is_html(&response) {
+        response = include::apply(&options.root, &exchange.served_path, response).await;
+    }
+
+    if let Some(versions) = &options.cache_bust_html
+        && is_html(&response)
+    {
+        response = versions
+            .apply(&options.root, &exchange.requested_path, response)
+            .await;
+    }
+
+    if options.csp_nonce && is_html(&response) {
+        response = nonce::apply(response).await;
+    }
+
+    response
+}
+
+/// Serves the file from disk and labels it with its content type.
+async fn from_disk(
+    options: &Options,
+    exchange: &Exchange,
+    request: Request<Body>,
+    next: Next,
+    timing: &mut ServerTiming,
+) -> Response {
+    let method = request.method().clone();
+
+    let started = Instant::now();
+    let mut response = next.run(request).await;
+    timing.stream = Some(started.elapsed());
+
+    if options.suggest_on_404
+        && response.status() == StatusCode::NOT_FOUND
+        && let Some(page) = suggest::page(&options.root, &exchange.requested_path).await
+    {
+        let page = DefaultFile {
+            content_type: HeaderValue::from_static("text/html; charset=utf-8"),
+            body: page.into(),
+        };
+        let mut response = generated_file(options, &page, &method);
+        *response.status_mut() = StatusCode::NOT_FOUND;
+        return response;
+    }
+
+    let started = Instant::now();
+    label(options, exchange, &mut response).await;
+    timing.infer = Some(started.elapsed());
+    response
+}
+
+/// Sets the content type inferred for the served file, or read from its sidecar file.
+async fn label(options: &Options, exchange: &Exchange, response: &mut Response) {
+    // IO errors are the only server errors from disk; their JSON keeps its type.
+    if options.json_errors && response.status().is_server_error() {
+        return;
+    }
+    set_content_type(response, &exchange.served_path);
+    if let Some(resolvers) = &options.content_type_resolvers {
+        if response.status().is_success()
+            && let Some(mime) = resolver::resolve(
+                Arc::clone(&resolvers.0),
+                &options.root,
+                &exchange.served_path,
+            )
+            .await
+        {
+            response.headers_mut().insert(header::CONTENT_TYPE, mime);
+        }
+        return;
+    }
+    if options.extension_strategy == ExtensionStrategy::MostSpecific
+        && response.status().is_success()
+        && let Some(mime) = extension::compound_content_type(&exchange.served_path)
+    {
+        response
+            .headers_mut()
+            .insert(header::CONTENT_TYPE, HeaderValue::from_static(mime));
+    }
+    if options.sidecar_mime
+        && response.status().is_success()
+        && matches!(
+            infer_content_type(&exchange.served_path),
+            "unknown" | "application/octet-stream"
+        )
+        && let Some(mime) = fs::sidecar_mime(&options.root, &exchange.served_path).await
+    {
+        response.headers_mut().insert(header::CONTENT_TYPE, mime);
+    }
+}
+
+/// Returns whether the client is on the maintenance allowlist.
+fn allowlisted(options: &Options, request: &Request<Body>) -> bool {
+    !options.maintenance_allowlist.is_empty()
+        && limit::client(request, options.client_ip_header.as_ref()).is_some_and(|client| {
+            options
+                .maintenance_allowlist
+                .iter()
+                .any(|range| range.contains(client))
+        })
+}
+
+/// Returns the redirect for the request, if any option calls for one.
+///
+/// Redirects are resolved in a single pass: a request for the wrong host that also
+/// matches the path redirect `target` is sent straight to the final location, and a
+/// path redirect whose target would itself be redirected, such as `/docs/` to the
+/// directory `/docs` and back, is refused with a `500 Internal Server Error` instead
+/// of sending clients around a chain or loop.
+fn redirect_for(
+    options: &Options,
+    request: &Request<Body>,
+    target: Option<(StatusCode, String)>,
+) -> Option<Response> {
+    if let Some((_, location)) = &target
+        && redirect::local_path(location).is_some_and(|path| path_redirect_applies(options, path))
+    {
+        #[cfg(feature = "tracing")]
+        error!(from = %request.uri().path(), to = %location, "Redirect target is redirected again; refusing to chain redirects");
+        return Some(StatusCode::INTERNAL_SERVER_ERROR.into_response());
+    }
+
+    let host = options
+        .canonical_host
+        .as_deref()
+        .filter(|host| redirect::is_off_host(request, host));
+    match (host, target) {
+        (Some(host), Some((status, location))) if redirect::local_path(&location).is_some() => {
+            Some(redirect::to(status, &format!("//{host}{location}")))
+        }
+        (Some(host), None) => {
+            let location = format!("//{host}{}", redirect::original_path_and_query(request));
+            Some(redirect::to(StatusCode::MOVED_PERMANENTLY, &location))
+        }
+        (_, Some((status, location))) => Some(redirect::to(status, &location)),
+        (None, None) => None,
+    }
+}
+
+/// Returns the status and location of the root or rule redirect matching the URI.
+///
+/// A local location naming a directory gets the trailing slash the directory redirect
+/// would add, so clients are sent there directly.
+async fn path_redirect(options: &Options, uri: &Uri) -> Option<(StatusCode, String)> {
+    let path = uri.path();
+    let (status, mut location) = if let Some(location) = &options.root_redirect
+        && path == "/"
+    {
+        let location = match uri.query() {
+            Some(query) => format!("{location}?{query}"),
+            None => location.clone(),
+        };
+        (StatusCode::FOUND, location)
+    } else {
+        options
+            .redirects
+            .iter()
+            .find(|rule| rule.from == path && rule.status != StatusCode::OK)
+            .map(|rule| (rule.status, rule.to.clone()))?
+    };
+
+    if let Some(path) = redirect::local_path(&location)
+        && directory_redirect_applies(options, path).await
+    {
+        location = format!("{path}/{}", &location[path.len()..]);
+    }
+    Some((status, location))
+}
+
+/// Returns whether a request for `path` gets the redirect appending `/` to a directory.
+///
+/// Rewrite rules may map a directory path onto a file, so they take precedence. Only
+/// paths whose last segment has no extension are looked up, so requests for files
+/// don't pay for a `stat`; `ServeDir` still redirects directories named like files.
+async fn directory_redirect_applies(options: &Options, path: &str) -> bool {
+    !path.ends_with('/')
+        && !has_extension(path)
+        && !options.redirects.iter().any(|rule| rule.from == path)
+        && fs::is_dir(&options.root, path).await
+}
+
+/// Returns whether a request for `path` would be answered with a path redirect.
+fn path_redirect_applies(options: &Options, path: &str) -> bool {
+    (options.root_redirect.is_some() && path == "/")
+        || options
+            .redirects
+            .iter()
+            .any(|rule| rule.from == path && rule.status != StatusCode::OK)
+}
+
+/// Rewrites the request URI to the file that should be served.
+async fn rewrite_request(options: &Options, request: &mut Request<Body>) {
+    if let Some(rule) = options
+        .redirects
+        .iter()
+        .find(|rule| rule.from == request.uri().path() && rule.status == StatusCode::OK)
+    {
+        rewrite_path(request, &rule.to);
+    }
+
+    let route_like = !has_extension(request.uri().path());
+    // Resolving the index here also makes `ServeDir` evaluate conditional headers
+    // against the index file's metadata instead of the directory's.
+    if request.uri().path().ends_with('/') {
+        let index = format!("{}{}", request.uri().path(), options.index_file);
+        rewrite_path(request, &index);
+    }
+
+    if options.spa_fallback && route_like && !fs::exists(&options.root, request.uri().path()).await
+    {
+        rewrite_path(request, "/index.html");
+    }
+
+    if !options.language_chain.is_empty() {
+        negotiate_language_variant(options, request).await;
+    }
+
+    if options.image_negotiation {
+        negotiate_image_format(options, request).await;
+    }
+}
+
+/// Returns a response produced without serving a file from disk, if one applies.
+async fn generated(
+    options: &Options,
+    exchange: &Exchange,
+    method: &Method,
+    seat: Option<&Seat>,
+) -> Option<Response> {
+    if options.regular_files_only && fs::is_special(&options.root, &exchange.served_path).await {
+        return Some(StatusCode::NOT_FOUND.into_response());
+    }
+
+    if jsonp_callback(options, exchange).is_some_and(|callback| !jsonp::is_valid(callback)) {
+        return Some((StatusCode::BAD_REQUEST, "invalid JSONP callback").into_response());
+    }
+
+    if let Some(etag) = &exchange.etag
+        && let Some(response) =
+            etag::precondition(&exchange.request_headers, etag, options.honor_if_match)
+    {
+        return Some(response);
+    }
+
+    if let Some(default) = options.default_files.get(&exchange.requested_path)
+        && !fs::is_file(&options.root, &exchange.requested_path).await
+    {
+        return Some(generated_file(options, default, method));
+    }
+
+    #[cfg(feature = "compression")]
+    if let Some(response) = decompressed(options, exchange, method).await {
+        return Some(response);
+    }
+
+    #[cfg(feature = "cdn_fallback")]
+    if let Some(upstream) = &options.cdn_fallback
+        && serves_representation(method)
+        && let Some(path) = cdn::forwarded_path(&exchange.served_path)
+        && !fs::exists(&options.root, &exchange.served_path).await
+    {
+        let path_and_query = match &exchange.query {
+            Some(query) => format!("{path}?{query}"),
+            None => path,
+        };
+        return Some(upstream.fetch(method, &path_and_query).await);
+    }
+
+    if serves_from_memory(options, exchange, method)
+        && let Some(file) = in_memory(options, exchange, seat).await
+    {
+        let body = if method == Method::HEAD {
+            Body::empty()
+        } else {
+            Body::from(file.body.clone())
+        };
+        let mut response = Response::new(body);
+        let headers = response.headers_mut();
+        headers.insert(header::CONTENT_LENGTH, file.body.len().into());
+        // Range requests bypass memory, but `ServeDir` still answers them.
+        headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+        if let Ok(modified) = HeaderValue::try_from(httpdate::fmt_http_date(file.modified)) {
+            headers.insert(header::LAST_MODIFIED, modified);
+        }
+        label(options, exchange, &mut response).await;
+        return Some(response);
+    }
+
+    None
+}
+
+/// Returns the served file from the memory cache, or from a read shared with
+/// concurrent requests, when either is enabled and applies.
+async fn in_memory(options: &Options, exchange: &Exchange, seat: Option<&Seat>) -> Option<Cached> {
+    if let Some(store) = &options.memory_cache
+        && let Some(file) = store
+            .get(
+                &options.root,
+                &exchange.served_path,
+                exchange.extension.as_deref(),
+            )
+            .await
+    {
+        return Some(file);
+    }
+
+    match seat {
+        Some(seat) => seat.read(&options.root).await,
+        None => None,
+    }
+}
+
+/// Returns whether the request can be answered from memory.
+///
+/// Conditional and range requests are left to `ServeDir`, as are requests that may
+/// be answered with a precompressed variant of the file.
+fn serves_from_memory(options: &Options, exchange: &Exchange, method: &Method) -> bool {
+    let headers = &exchange.request_headers;
+    let precompressed =
+        (options.precompressed_gzip || options.precompressed_br || options.precompressed_zstd)
+            && headers.contains_key(header::ACCEPT_ENCODING);
+    serves_representation(method)
+        && !precompressed
+        && !headers.contains_key(header::RANGE)
+        && !headers.contains_key(header::IF_MODIFIED_SINCE)
+        && !headers.contains_key(header::IF_UNMODIFIED_SINCE)
+}
+
+/// Serves in-memory content with the `Cache-Control` configured for generated responses.
+fn generated_file(options: &Options, file: &DefaultFile, method: &Method) -> Response {
+    let mut response = file.response(method);
+    response.headers_mut().insert(
+        header::CACHE_CONTROL,
+        options.generated_cache_control.clone(),
+    );
+    response
+}
+
+/// Streams the decoded contents of a precompressed-only file, if enabled and applicable.
+#[cfg(feature = "compression")]
+async fn decompressed(options: &Options, exchange: &Exchange, method: &Method) -> Option<Response> {
+    if !options.auto_decompress {
+        return None;
+    }
+
+    let mut response = decompress::serve(
+        &options.root,
+        method,
+        &exchange.served_path,
+        &exchange.request_headers,
+        |coding| match coding {
+            "br" => options.precompressed_br,
+            _ => options.precompressed_gzip,
+        },
+    )
+    .await?;
+    let content_type = HeaderValue::from_static(infer_content_type(&exchange.served_path));
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, content_type);
+    Some(response)
+}
+
+/// Applies the configured response headers.
+async fn decorate(options: &Options, exchange: &Exchange, response: &mut Response) {
+    if let Some(extension) = &exchange.extension {
+        let headers = response.headers_mut();
+        if options.content_type_resolvers.is_none()
+            && let Some(mime) = options.content_types.get(extension)
+        {
+            headers.insert(header::CONTENT_TYPE, mime.clone());
+        }
+        if let Some(value) = options.cache_control.get(extension)
+            && !headers.contains_key(header::CACHE_CONTROL)
+        {
+            headers.insert(header::CACHE_CONTROL, value.clone());
+        }
+    }
+
+    if let Some(policy) = &options.cache_policy
+        && (response.status().is_success() || response.status() == StatusCode::NOT_MODIFIED)
+        && !response.headers().contains_key(header::CACHE_CONTROL)
+    {
+        let value = policy.value(&exchange.served_path, exchange.extension.as_deref());
+        response.headers_mut().insert(header::CACHE_CONTROL, value);
+    }
+
+    if options.disable_ranges {
+        response.headers_mut().remove(header::ACCEPT_RANGES);
+    }
+
+    if let Some(policy) = &options.cache_by_age
+        && response.status().is_success()
+        && !response.headers().contains_key(header::CACHE_CONTROL)
+        && let Some(age) = file_age(&options.root, &exchange.served_path).await
+        && let Ok(value) = HeaderValue::try_from((policy.0)(age))
+    {
+        response.headers_mut().insert(header::CACHE_CONTROL, value);
+    }
+
+    if let Some(jitter) = options.cache_jitter
+        && let Some(value) = response.headers().get(header::CACHE_CONTROL)
+        && let Some(value) = jitter::apply(value, jitter)
+    {
+        response.headers_mut().insert(header::CACHE_CONTROL, value);
+    }
+
+    // Precompressed variants already carry a `Content-Encoding` and are served as-is.
+    #[cfg(feature = "compression")]
+    if !response.headers().contains_key(header::CONTENT_ENCODING)
+        && let Some(marker) = options.compression.marker(exchange.extension.as_deref())
+    {
+        response.extensions_mut().insert(marker);
+    }
+
+    if let Some(etag) = &exchange.etag {
+        set_etag(response, etag);
+    }
+
+    if let Some(algorithm) = options.digest
+        && response.status() == StatusCode::OK
+        && !may_be_encoded(response)
+        && let Some(value) = digest::compute(&options.root, &exchange.served_path, algorithm).await
+    {
+        response.headers_mut().insert(DIGEST, value);
+    }
+
+    if let Some((_, value)) = options
+        .clear_site_data
+        .iter()
+        .find(|(target, _)| *target == exchange.requested_path)
+    {
+        response
+            .headers_mut()
+            .insert(CLEAR_SITE_DATA, value.clone());
+    }
+
+    if !options.language_chain.is_empty() {
+        response
+            .headers_mut()
+            .append(header::VARY, HeaderValue::from_static("accept-language"));
+    }
+
+    if options.image_negotiation
+        && image::is_negotiable(path_extension(&exchange.requested_path).as_deref())
+    {
+        response
+            .headers_mut()
+            .append(header::VARY, HeaderValue::from_static("accept"));
+    }
+
+    if !options.html_headers.is_empty() && is_html(response) {
+        for (name, value) in &options.html_headers {
+            response.headers_mut().append(name, value.clone());
+        }
+    }
+
+    if let Some(base_url) = &options.canonical_link
+        && response.status() == StatusCode::OK
+        && is_html(response)
+        && let Some(value) = canonical_link(base_url, &options.index_file, &exchange.requested_path)
+    {
+        response.headers_mut().append(header::LINK, value);
+    }
+
+    if options.allow_plaintext_view && is_html(response) {
+        let headers = response.headers_mut();
+        headers.append(header::VARY, HeaderValue::from_static("accept"));
+        if plaintext::preferred(&exchange.request_headers) {
+            headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+            headers.insert(
+                header::X_CONTENT_TYPE_OPTIONS,
+                HeaderValue::from_static("nosniff"),
+            );
+        }
+    }
+}
+
+/// Returns the JSONP callback requested for a JSON file, when JSONP is allowed.
+fn jsonp_callback<'a>(options: &Options, exchange: &'a Exchange) -> Option<&'a str> {
+    if !options.allow_jsonp || exchange.extension.as_deref() != Some("json") {
+        return None;
+    }
+    jsonp::callback(exchange.query.as_deref())
+}
+
+/// Sets the `ETag` of a successful or not-modified response that has none.
+///
+/// Responses that are or may be content-coded get the weak form of the tag, as
+/// their bytes differ from the file's.
+fn set_etag(response: &mut Response, etag: &str) {
+    let status = response.status();
+    if !(status.is_success() || status == StatusCode::NOT_MODIFIED)
+        || response.headers().contains_key(header::ETAG)
+    {
+        return;
+    }
+
+    let etag = if may_be_encoded(response) {
+        etag::weak(etag)
+    } else {
+        etag.to_owned()
+    };
+    if let Ok(value) = HeaderValue::try_from(etag) {
+        response.headers_mut().insert(header::ETAG, value);
+    }
+}
+
+/// Returns the entity tag of the file at the served path, from the manifest if it
+/// lists the file.
+async fn entity_tag(options: &Options, served_path: &str) -> Option<String> {
+    if let Some(manifest) = &options.etag_manifest
+        && let Some(tag) = manifest.get(&options.root, served_path).await
+    {
+        return Some(tag);
+    }
+    match &options.etag_content_only {
+        Some(tags) => {
+            tags.compute(&options.root, served_path, &options.etag_prefix)
+                .await
+        }
+        None => etag::compute(&options.root, served_path, &options.etag_prefix).await,
+    }
+}
+
+/// Returns whether the method is answered with the file's representation.
+///
+/// `HEAD` goes through the same validator computation as `GET`, from the same file
+/// metadata, so its `ETag` and `Last-Modified` are those the `GET` would carry.
+fn serves_representation(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD)
+}
+
+/// Returns whether the response is or may become content-coded, so that its bytes
+/// differ from the file's.
+fn may_be_encoded(response: &Response) -> bool {
+    let encoded = response.headers().contains_key(header::CONTENT_ENCODING);
+    #[cfg(feature = "compression")]
+    let encoded = encoded || response.extensions().get::<Compress>().is_some();
+    encoded
+}
+
+/// Rewrites the request to the first existing language variant of the requested file.
+async fn negotiate_language_variant(options: &Options, request: &mut Request<Body>) {
+    let path = request.uri().path().to_owned();
+    for language in language::candidates(request.headers(), &options.language_chain) {
+        let Some(variant) = language::variant_path(&path, &language) else {
+            return;
+        };
+        if fs::is_file(&options.root, &variant).await {
+            rewrite_path(request, &variant);
+            return;
+        }
+    }
+}
+
+/// Rewrites an image request to the most preferred format sibling the client accepts.
+async fn negotiate_image_format(options: &Options, request: &mut Request<Body>) {
+    let path = request.uri().path().to_owned();
+    if !image::is_negotiable(path_extension(&path).as_deref()) {
+        return;
+    }
+
+    for extension in image::candidates(request.headers()) {
+        if let Some(sibling) = image::sibling_path(&path, extension)
+            && fs::is_file(&options.root, &sibling).await
+        {
+            rewrite_path(request, &sibling);
+            return;
+        }
+    }
+}
+
+/// Returns the time elapsed since the file at the request path was last modified.
+async fn file_age(root: &Path, request_path: &str) -> Option<Duration> {
+    let path = fs::resolve(root, request_path)?;
+    let modified = tokio::fs::metadata(path).await.ok()?.modified().ok()?;
+    Some(
+        SystemTime::now()
+            .duration_since(modified)
+            .unwrap_or_default(),
+    )
+}
+
+/// Returns whether a successful response would reach the client uncompressed even
+/// though its `Accept-Encoding` refuses the identity coding (`identity;q=0`).
+fn refuses_identity(request_headers: &HeaderMap, response: &Response) -> bool {
+    if !response.status().is_success()
+        || response.headers().contains_key(header::CONTENT_ENCODING)
+        || encoding::accepts_identity(request_headers)
+    {
+        return false;
+    }
+
+    #[cfg(feature = "compression")]
+    if response
+        .extensions()
+        .get::<Compress>()
+        .is_some_and(|marker| marker.applies(request_headers))
+    {
+        return false;
+    }
+
+    true
+}
+
+/// Returns whether the last segment of the request path has a file extension.
+fn has_extension(path: &str) -> bool {
+    path.rsplit('/')
+        .next()
+        .is_some_and(|name| name.contains('.'))
+}
+
+/// Returns the `Link` naming the canonical URL of the page at `path`, under which
+/// the directory path and its index file are the same page.
+fn canonical_link(base_url: &str, index_file: &str, path: &str) -> Option<HeaderValue> {
+    let path = path
+        .strip_suffix(index_file)
+        .filter(|directory| directory.ends_with('/'))
+        .unwrap_or(path);
+    HeaderValue::try_from(format!("<{base_url}{path}>; rel=\"canonical\"")).ok()
+}
+
+/// Returns whether the response is labelled as an HTML document.
+fn is_html(response: &Response) -> bool {
+    response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/html"))
+}
+
+/// Replaces the path of the request URI, preserving its query string.
+fn rewrite_path(request: &mut Request<Body>, path: &str) {
+    let path_and_query = match request.uri().query() {
+        Some(query) => format!("{path}?{query}"),
+        None => path.to_owned(),
+    };
+    let Ok(path_and_query) = PathAndQuery::try_from(path_and_query) else {
+        return;
+    };
+
+    let mut parts = request.uri().clone().into_parts();
+    parts.path_and_query = Some(path_and_query);
+    if let Ok(uri) = Uri::from_parts(parts) {
+        *request.uri_mut() = uri;
+    }
+}
@@ -0,0 +1,38 @@
+//! Parsing of Apache-style `mime.types` files.
+
+use axum::http::HeaderValue;
+use std::io;
+
+/// Parses `mime.types` contents into pairs of lowercased extensions and media types.
+///
+/// Each line holds a media type followed by any number of extensions, separated by
+/// whitespace; `#` starts a comment, and types listed without extensions are skipped.
+/// A later line for the same extension replaces an earlier one.
+pub(crate) fn parse(contents: &str) -> io::Result<Vec<(String, HeaderValue)>> {
+    let mut mappings = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut fields = line.split_whitespace();
+        let Some(media_type) = fields.next() else {
+            continue;
+        };
+
+        let invalid = |reason: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("mime.types line {}: {reason}", number + 1),
+            )
+        };
+        if !media_type.contains('/') {
+            return Err(invalid("expected `type/subtype extension...`"));
+        }
+        let media_type =
+            HeaderValue::from_str(media_type).map_err(|_| invalid("invalid media type"))?;
+
+        for extension in fields {
+            let extension = extension.trim_start_matches('.').to_ascii_lowercase();
+            mappings.push((extension, media_type.clone()));
+        }
+    }
+    Ok(mappings)
+}
@@ -0,0 +1,40 @@
+//! Ranges of client addresses written in CIDR notation.
+
+use std::net::IpAddr;
+
+/// A block of addresses, such as `10.0.0.0/8` or `2001:db8::/32`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct IpRange {
+    network: IpAddr,
+    prefix: u32,
+}
+
+impl IpRange {
+    /// Parses a range, where a bare address such as `203.0.113.7` is a range of one.
+    pub(crate) fn parse(range: &str) -> Option<Self> {
+        let (address, prefix) = match range.trim().split_once('/') {
+            Some((address, prefix)) => (address, Some(prefix.parse::<u32>().ok()?)),
+            None => (range.trim(), None),
+        };
+        let network = address.parse::<IpAddr>().ok()?;
+        let bits = if network.is_ipv4() { 32 } else { 128 };
+        let prefix = prefix.unwrap_or(bits);
+        (prefix <= bits).then_some(Self { network, prefix })
+    }
+
+    /// Returns whether `address` is in the range. IPv4 clients reached over an
+    /// IPv4-mapped IPv6 address match IPv4 ranges.
+    pub(crate) fn contains(&self, address: IpAddr) -> bool {
+        match (self.network, address.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(address)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix).unwrap_or(0);
+                u32::from(network) & mask == u32::from(address) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(address)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix).unwrap_or(0);
+                u128::from(network) & mask == u128::from(address) & mask
+            }
+            _ => false,
+        }
+    }
+}
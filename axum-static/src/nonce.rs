@@ -0,0 +1,136 @@
+//! Per-request `Content-Security-Policy` nonces for HTML responses.
+
+use axum::{
+    body::Body,
+    http::{HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+
+use crate::buffer::{self, Buffered};
+
+/// The placeholder replaced by the nonce in served HTML.
+const PLACEHOLDER: &str = "__CSP_NONCE__";
+
+/// Injects a fresh nonce into the HTML body and the `Content-Security-Policy` header.
+///
+/// Policies already on the response get the nonce in their `script-src` and
+/// `style-src` directives; otherwise the response carries a `script-src` policy
+/// allowing only nonced scripts.
+///
+/// The nonce differs on every request, so the response is marked `no-store` and its
+/// validators are dropped; a cached copy would carry a stale nonce.
+pub(crate) async fn apply(response: Response) -> Response {
+    let Some(nonce) = generate() else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+
+    let (mut parts, body) = response.into_parts();
+    let body = match buffer::read(body).await {
+        Ok(Buffered::Complete(body)) => body,
+        Ok(Buffered::Oversized(body)) => return Response::from_parts(parts, body),
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    let body = replace(&body, PLACEHOLDER.as_bytes(), nonce.as_bytes());
+
+    let headers = &mut parts.headers;
+    let policies: Vec<HeaderValue> = headers
+        .get_all(header::CONTENT_SECURITY_POLICY)
+        .iter()
+        .map(|policy| {
+            match policy.to_str() {
+                Ok(policy) => HeaderValue::from_str(&merge(policy, &nonce)).ok(),
+                Err(_) => None,
+            }
+            .unwrap_or_else(|| policy.clone())
+        })
+        .collect();
+    if policies.is_empty() {
+        if let Ok(policy) = HeaderValue::from_str(&format!("script-src 'nonce-{nonce}'")) {
+            headers.insert(header::CONTENT_SECURITY_POLICY, policy);
+        }
+    } else {
+        headers.remove(header::CONTENT_SECURITY_POLICY);
+        for policy in policies {
+            headers.append(header::CONTENT_SECURITY_POLICY, policy);
+        }
+    }
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    buffer::drop_validators(headers);
+    Response::from_parts(parts, Body::from(body))
+}
+
+/// Returns `body` with every occurrence of `from` replaced by `to`.
+///
+/// The body is matched as bytes, so documents in encodings other than UTF-8 pass
+/// through unchanged apart from the replacements.
+fn replace(body: &[u8], from: &[u8], to: &[u8]) -> Vec<u8> {
+    let mut replaced = Vec::with_capacity(body.len());
+    let mut rest = body;
+    while let Some(at) = buffer::find(rest, from) {
+        replaced.extend_from_slice(&rest[..at]);
+        replaced.extend_from_slice(to);
+        rest = &rest[at + from.len()..];
+    }
+    replaced.extend_from_slice(rest);
+    replaced
+}
+
+/// Adds the nonce to the `script-src` and `style-src` directives of a policy set
+/// by the application, leaving its other directives as they are.
+///
+/// A policy with neither directive is returned unchanged.
+fn merge(policy: &str, nonce: &str) -> String {
+    policy
+        .split(';')
+        .map(|directive| {
+            let name = directive.split_whitespace().next().unwrap_or_default();
+            if name.eq_ignore_ascii_case("script-src") || name.eq_ignore_ascii_case("style-src") {
+                format!("{} 'nonce-{nonce}'", directive.trim_end())
+            } else {
+                directive.to_owned()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// Returns a base64-encoded nonce of 128 random bits from the operating system.
+fn generate() -> Option<String> {
+    let mut bytes = [0; 16];
+    getrandom::fill(&mut bytes).ok()?;
+    Some(STANDARD.encode(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merge, replace};
+
+    #[test]
+    fn replaces_placeholders_in_bytes_that_are_not_utf8() {
+        let body = b"<p>caf\xe9</p><script nonce=\"__CSP_NONCE__\"></script>__CSP_NONCE__";
+        assert_eq!(
+            replace(body, b"__CSP_NONCE__", b"abc"),
+            b"<p>caf\xe9</p><script nonce=\"abc\"></script>abc"
+        );
+    }
+
+    #[test]
+    fn adds_the_nonce_to_script_and_style_sources() {
+        assert_eq!(
+            merge(
+                "default-src 'self'; script-src 'self'; Style-Src https://cdn",
+                "abc"
+            ),
+            "default-src 'self'; script-src 'self' 'nonce-abc'; Style-Src https://cdn 'nonce-abc'"
+        );
+    }
+
+    #[test]
+    fn leaves_policies_without_script_or_style_sources_alone() {
+        assert_eq!(
+            merge("default-src 'self'; img-src *", "abc"),
+            "default-src 'self'; img-src *"
+        );
+    }
+}
@@ -0,0 +1,72 @@
+//! Unicode normalization of request paths.
+
+use percent_encoding::{AsciiSet, CONTROLS, percent_decode_str, utf8_percent_encode};
+use unicode_normalization::UnicodeNormalization;
+
+/// Characters percent-encoded in normalized path segments.
+const SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?');
+
+/// The Unicode normalization form request paths are converted to, matching how the
+/// file system stores names.
+///
+/// A name such as `café.txt` can be written with a precomposed `é` or with `e`
+/// followed by a combining accent. Most Linux and Windows file systems keep names as
+/// they were created, usually composed, while macOS has historically stored them
+/// decomposed.
+///
+/// ## Example
+///
+/// ```rust
+/// use axum_static::{NormalizationForm, StaticRouter};
+///
+/// let app = StaticRouter::new("static/")
+///     .unicode_normalize(NormalizationForm::Nfc)
+///     .build();
+/// ```
+///
+/// # Features
+///
+/// This type is only available when the `unicode_normalize` feature is enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalizationForm {
+    /// Canonical composition, where `é` is a single code point.
+    Nfc,
+    /// Canonical decomposition, where `é` is `e` followed by a combining acute accent.
+    Nfd,
+}
+
+/// Returns the request path with each segment converted to `form`, or `None` if it
+/// is already normalized.
+///
+/// Segments are normalized after percent-decoding and encoded again afterwards, so an
+/// encoded `/` stays part of its segment.
+pub(crate) fn path(path: &str, form: NormalizationForm) -> Option<String> {
+    let normalized = path
+        .split('/')
+        .map(|segment| {
+            let decoded = percent_decode_str(segment).decode_utf8_lossy();
+            if decoded.is_ascii() {
+                return segment.to_owned();
+            }
+            let normalized: String = match form {
+                NormalizationForm::Nfc => decoded.nfc().collect(),
+                NormalizationForm::Nfd => decoded.nfd().collect(),
+            };
+            if normalized == decoded {
+                segment.to_owned()
+            } else {
+                utf8_percent_encode(&normalized, SEGMENT).to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+    (normalized != path).then_some(normalized)
+}
@@ -0,0 +1,229 @@
+//! Request-time configuration shared between the builder and the middleware.
+
+use axum::body::{Body, Bytes};
+use axum::http::{HeaderMap, HeaderName, HeaderValue, Method, header};
+use axum::response::Response;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::cache;
+use crate::cache_bust::Versions;
+#[cfg(feature = "cdn_fallback")]
+use crate::cdn;
+use crate::coalesce::Flights;
+#[cfg(feature = "compression")]
+use crate::compression;
+use crate::digest::DigestAlgorithm;
+use crate::etag::{ContentTags, Manifest};
+use crate::extension::ExtensionStrategy;
+use crate::limit::ConnectionLimit;
+use crate::network::IpRange;
+#[cfg(feature = "unicode_normalize")]
+use crate::normalize::NormalizationForm;
+use crate::policy::CachePolicy;
+use crate::redirect;
+use crate::resolver::Resolver;
+use crate::source::AsyncFileSource;
+
+/// Computes a `Cache-Control` value from the age of the served file.
+pub(crate) type CacheByAge = dyn Fn(Duration) -> String + Send + Sync;
+
+/// Receives the served path and the bytes sent when a client disconnects mid-response.
+pub(crate) type OnDisconnect = dyn Fn(&str, u64) + Send + Sync;
+
+/// A user-supplied callback stored in the router options.
+pub(crate) struct Callback<F: ?Sized>(pub(crate) Arc<F>);
+
+impl<F: ?Sized> Clone for Callback<F> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<F: ?Sized> fmt::Debug for Callback<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Callback(..)")
+    }
+}
+
+/// In-memory content served at a configured path.
+#[derive(Debug, Clone)]
+pub(crate) struct DefaultFile {
+    pub(crate) content_type: HeaderValue,
+    pub(crate) body: Bytes,
+}
+
+impl DefaultFile {
+    /// Returns the response serving the content, with an empty body for `HEAD`.
+    pub(crate) fn response(&self, method: &Method) -> Response {
+        let body = if method == Method::HEAD {
+            Body::empty()
+        } else {
+            Body::from(self.body.clone())
+        };
+        let mut response = Response::new(body);
+        response
+            .headers_mut()
+            .insert(header::CONTENT_TYPE, self.content_type.clone());
+        response
+    }
+}
+
+/// Request-time configuration shared with the router middleware.
+///
+/// Each builder toggle is an independent flag, so this is a plain bag of settings.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone)]
+pub(crate) struct Options {
+    pub(crate) root: PathBuf,
+    pub(crate) failover_root: Option<PathBuf>,
+    pub(crate) file_source: Option<Callback<dyn AsyncFileSource>>,
+    pub(crate) embedded_fallback: Option<Callback<dyn AsyncFileSource>>,
+    #[cfg(feature = "cdn_fallback")]
+    pub(crate) cdn_fallback: Option<cdn::Upstream>,
+    pub(crate) status_endpoint: Option<(String, DefaultFile)>,
+    pub(crate) acme_challenges: HashMap<String, DefaultFile>,
+    pub(crate) connection_limit: Option<ConnectionLimit>,
+    pub(crate) client_ip_header: Option<HeaderName>,
+    pub(crate) deadline_header: Option<HeaderName>,
+    pub(crate) maintenance: bool,
+    pub(crate) maintenance_allowlist: Vec<IpRange>,
+    pub(crate) cors: bool,
+    pub(crate) cors_allow_methods: Vec<Method>,
+    pub(crate) cors_allow_headers: Vec<HeaderName>,
+    pub(crate) cors_max_age: Option<HeaderValue>,
+    pub(crate) retry_after: Option<HeaderValue>,
+    pub(crate) canonical_host: Option<String>,
+    pub(crate) canonical_link: Option<String>,
+    pub(crate) root_redirect: Option<String>,
+    pub(crate) redirects: Vec<redirect::Rule>,
+    #[cfg(feature = "unicode_normalize")]
+    pub(crate) unicode_normalize: Option<NormalizationForm>,
+    pub(crate) index_file: String,
+    pub(crate) spa_fallback: bool,
+    pub(crate) suggest_on_404: bool,
+    pub(crate) regular_files_only: bool,
+    pub(crate) read_retries: u32,
+    pub(crate) json_errors: bool,
+    pub(crate) memory_cache: Option<cache::Store>,
+    pub(crate) coalesce_requests: Option<Flights>,
+    pub(crate) disable_ranges: bool,
+    pub(crate) etag: bool,
+    pub(crate) etag_prefix: String,
+    pub(crate) etag_content_only: Option<ContentTags>,
+    pub(crate) etag_manifest: Option<Manifest>,
+    pub(crate) digest: Option<DigestAlgorithm>,
+    pub(crate) honor_if_match: bool,
+    pub(crate) default_files: HashMap<String, DefaultFile>,
+    pub(crate) generated_cache_control: HeaderValue,
+    pub(crate) precompressed_gzip: bool,
+    pub(crate) precompressed_br: bool,
+    pub(crate) precompressed_zstd: bool,
+    #[cfg(feature = "compression")]
+    pub(crate) auto_decompress: bool,
+    pub(crate) language_chain: Vec<String>,
+    pub(crate) image_negotiation: bool,
+    pub(crate) clear_site_data: Vec<(String, HeaderValue)>,
+    pub(crate) html_headers: HeaderMap,
+    pub(crate) server_timing: bool,
+    pub(crate) server_side_includes: bool,
+    pub(crate) cache_bust_html: Option<Versions>,
+    pub(crate) csp_nonce: bool,
+    pub(crate) allow_jsonp: bool,
+    pub(crate) strip_headers: Vec<HeaderName>,
+    pub(crate) content_types: HashMap<String, HeaderValue>,
+    pub(crate) extension_strategy: ExtensionStrategy,
+    pub(crate) sidecar_mime: bool,
+    pub(crate) content_type_resolvers: Option<Callback<[Box<dyn Resolver>]>>,
+    pub(crate) allow_plaintext_view: bool,
+    pub(crate) cache_control: HashMap<String, HeaderValue>,
+    pub(crate) cache_policy: Option<CachePolicy>,
+    pub(crate) cache_by_age: Option<Callback<CacheByAge>>,
+    pub(crate) cache_jitter: Option<u64>,
+    pub(crate) on_disconnect: Option<Callback<OnDisconnect>>,
+    #[cfg(feature = "compression")]
+    pub(crate) compression: compression::Settings,
+}
+
+impl Options {
+    /// Returns whether resolving the served file touches the filesystem.
+    pub(crate) fn stats_on_rewrite(&self) -> bool {
+        self.spa_fallback || !self.language_chain.is_empty() || self.image_negotiation
+    }
+
+    pub(crate) fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            failover_root: None,
+            file_source: None,
+            embedded_fallback: None,
+            #[cfg(feature = "cdn_fallback")]
+            cdn_fallback: None,
+            status_endpoint: None,
+            acme_challenges: HashMap::new(),
+            connection_limit: None,
+            client_ip_header: None,
+            deadline_header: None,
+            maintenance: false,
+            maintenance_allowlist: Vec::new(),
+            cors: false,
+            cors_allow_methods: vec![Method::GET, Method::HEAD, Method::OPTIONS],
+            cors_allow_headers: Vec::new(),
+            cors_max_age: None,
+            retry_after: None,
+            canonical_host: None,
+            canonical_link: None,
+            root_redirect: None,
+            redirects: Vec::new(),
+            #[cfg(feature = "unicode_normalize")]
+            unicode_normalize: None,
+            index_file: "index.html".to_owned(),
+            spa_fallback: false,
+            suggest_on_404: false,
+            regular_files_only: false,
+            read_retries: 0,
+            json_errors: false,
+            memory_cache: None,
+            coalesce_requests: None,
+            disable_ranges: false,
+            etag: false,
+            etag_prefix: String::new(),
+            etag_content_only: None,
+            etag_manifest: None,
+            digest: None,
+            honor_if_match: false,
+            default_files: HashMap::new(),
+            generated_cache_control: HeaderValue::from_static("private, no-store"),
+            precompressed_gzip: false,
+            precompressed_br: false,
+            precompressed_zstd: false,
+            #[cfg(feature = "compression")]
+            auto_decompress: false,
+            language_chain: Vec::new(),
+            image_negotiation: false,
+            clear_site_data: Vec::new(),
+            html_headers: HeaderMap::new(),
+            server_timing: false,
+            server_side_includes: false,
+            cache_bust_html: None,
+            csp_nonce: false,
+            allow_jsonp: false,
+            strip_headers: Vec::new(),
+            content_types: HashMap::new(),
+            extension_strategy: ExtensionStrategy::LastSegment,
+            sidecar_mime: false,
+            content_type_resolvers: None,
+            allow_plaintext_view: false,
+            cache_control: HashMap::new(),
+            cache_policy: None,
+            cache_by_age: None,
+            cache_jitter: None,
+            on_disconnect: None,
+            #[cfg(feature = "compression")]
+            compression: compression::Settings::default(),
+        }
+    }
+}
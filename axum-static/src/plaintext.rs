@@ -0,0 +1,53 @@
+//! `Accept` negotiation of plain-text views of HTML files.
+
+use axum::http::{HeaderMap, header};
+
+/// Returns whether the client prefers `text/plain` over `text/html`.
+///
+/// Each media type gets the quality of the most specific `Accept` entry matching it,
+/// so `text/plain, */*;q=0.8` prefers the plain-text view while a browser's usual
+/// `text/html, */*;q=0.8` does not. Equal qualities keep the HTML view.
+pub(crate) fn preferred(headers: &HeaderMap) -> bool {
+    quality(headers, "text", "plain") > quality(headers, "text", "html")
+}
+
+/// Returns the quality the client assigned to `kind/subtype`, or `0` if no entry
+/// matches it.
+fn quality(headers: &HeaderMap, kind: &str, subtype: &str) -> f32 {
+    let mut best = None::<(u8, f32)>;
+    for value in headers.get_all(header::ACCEPT) {
+        let Ok(value) = value.to_str() else {
+            continue;
+        };
+        for entry in value.split(',') {
+            let mut params = entry.split(';').map(str::trim);
+            let Some((entry_kind, entry_subtype)) =
+                params.next().unwrap_or_default().split_once('/')
+            else {
+                continue;
+            };
+            let specificity = match (entry_kind, entry_subtype) {
+                ("*", "*") => 0,
+                (entry_kind, "*") if entry_kind.eq_ignore_ascii_case(kind) => 1,
+                (entry_kind, entry_subtype)
+                    if entry_kind.eq_ignore_ascii_case(kind)
+                        && entry_subtype.eq_ignore_ascii_case(subtype) =>
+                {
+                    2
+                }
+                _ => continue,
+            };
+            let Some(q) = params
+                .find_map(|param| param.strip_prefix("q=").or(param.strip_prefix("Q=")))
+                .map_or(Some(1.0), |q| q.parse::<f32>().ok())
+            else {
+                continue;
+            };
+
+            if best.is_none_or(|(best, _)| specificity > best) {
+                best = Some((specificity, q));
+            }
+        }
+    }
+    best.map_or(0.0, |(_, q)| q)
+}
@@ -0,0 +1,130 @@
+//! `Cache-Control` policies chosen by file type.
+
+use axum::http::HeaderValue;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// The `Cache-Control` of fingerprinted assets, whose contents never change.
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+
+/// `Cache-Control` values chosen by the served file's type.
+///
+/// Files are matched in order against:
+///
+/// 1. A rule for their extension, which for `.html` and `.htm` is `no-cache` unless
+///    [overridden](Self::extension), so pages are always revalidated.
+/// 2. With [`immutable_fingerprints`](Self::immutable_fingerprints), a content hash in
+///    their name, such as `app.8f3c21ab.js` or `index-BQrd3x1a.js`, which is cached for
+///    a year as `immutable`.
+/// 3. The default `public, max-age=<seconds>`.
+///
+/// ## Example
+///
+/// ```rust
+/// use axum_static::{CachePolicy, static_router_cached};
+/// use std::time::Duration;
+///
+/// let policy = CachePolicy::new(Duration::from_secs(3600))
+///     .extension("json", "no-store")
+///     .immutable_fingerprints(true);
+/// let app = static_router_cached("static/", policy);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CachePolicy {
+    default: HeaderValue,
+    extensions: HashMap<String, HeaderValue>,
+    fingerprints: bool,
+}
+
+impl CachePolicy {
+    /// Creates a policy caching files without a more specific rule for `max_age`.
+    #[must_use]
+    pub fn new(max_age: Duration) -> Self {
+        let no_cache = HeaderValue::from_static("no-cache");
+        Self {
+            default: HeaderValue::try_from(format!("public, max-age={}", max_age.as_secs()))
+                .unwrap_or_else(|_| no_cache.clone()),
+            extensions: HashMap::from([
+                ("html".to_owned(), no_cache.clone()),
+                ("htm".to_owned(), no_cache),
+            ]),
+            fingerprints: false,
+        }
+    }
+
+    /// Sets the `Cache-Control` of files with the given extension, replacing the
+    /// built-in rules for them.
+    ///
+    /// # Arguments
+    ///
+    /// * `extension` - The file extension without the leading dot, such as `css`.
+    /// * `value` - The `Cache-Control` value, such as `public, max-age=600`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` is not a valid header value.
+    #[must_use]
+    pub fn extension(mut self, extension: &str, value: &str) -> Self {
+        let value = HeaderValue::from_str(value).expect("invalid Cache-Control header value");
+        self.extensions
+            .insert(extension.to_ascii_lowercase(), value);
+        self
+    }
+
+    /// Caches files whose names carry a content hash for a year as `immutable`.
+    ///
+    /// A name carries a hash when a `.` or `-` separated part before its extension is
+    /// 8, 16, 20, 32, 40 or 64 hex digits with at least one letter and one digit, such
+    /// as `app.8f3c21ab.js`, or 8 base32 or base64url characters with at least one
+    /// uppercase letter and one digit, such as `index-BQrd3x1a.js`. Dated and versioned
+    /// names such as `photo-20231201.jpg` or `app.v1beta2.js` don't, but a build that
+    /// puts a hash-like part in names of files it changes in place must leave this off,
+    /// as browsers keep an `immutable` copy without revalidating it.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether hashed names are cached as immutable.
+    #[must_use]
+    pub fn immutable_fingerprints(mut self, enabled: bool) -> Self {
+        self.fingerprints = enabled;
+        self
+    }
+
+    /// Returns the `Cache-Control` of the file at the request path, whose lowercased
+    /// extension is `extension`.
+    pub(crate) fn value(&self, path: &str, extension: Option<&str>) -> HeaderValue {
+        if let Some(value) = extension.and_then(|extension| self.extensions.get(extension)) {
+            value.clone()
+        } else if self.fingerprints && is_fingerprinted(path) {
+            HeaderValue::from_static(IMMUTABLE)
+        } else {
+            self.default.clone()
+        }
+    }
+}
+
+/// Returns whether the file name at the end of `path` carries a content hash.
+fn is_fingerprinted(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or_default();
+    let Some((stem, _)) = name.rsplit_once('.') else {
+        return false;
+    };
+    stem.split(['.', '-']).skip(1).any(is_hash)
+}
+
+/// Returns whether `part` of a file name looks like a hex, base32 or base64url digest.
+fn is_hash(part: &str) -> bool {
+    let bytes = part.as_bytes();
+    if !bytes.iter().any(u8::is_ascii_digit) {
+        return false;
+    }
+    let hex = matches!(bytes.len(), 8 | 16 | 20 | 32 | 40 | 64)
+        && bytes.iter().all(u8::is_ascii_hexdigit)
+        && bytes.iter().any(u8::is_ascii_alphabetic);
+    let encoded = bytes.len() == 8
+        && bytes
+            .iter()
+            .all(|&byte| byte.is_ascii_alphanumeric() || byte == b'_')
+        && bytes.iter().any(u8::is_ascii_uppercase);
+    hex || encoded
+}
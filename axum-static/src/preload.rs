@@ -0,0 +1,64 @@
+//! `Link: rel=preload` hints for HTML responses.
+
+use axum::http::HeaderValue;
+
+/// A resource announced to clients via a `Link: rel=preload` header.
+///
+/// ## Example
+///
+/// ```rust
+/// use axum_static::{PreloadLink, StaticRouter};
+///
+/// let app = StaticRouter::new("static/")
+///     .preload_links([PreloadLink::new("/app.css", "style")])
+///     .build();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreloadLink {
+    /// The URL of the resource, such as `/app.css`.
+    pub href: String,
+    /// The request destination announced via the `as` attribute, such as `style` or `script`.
+    pub destination: String,
+    /// Whether HTTP/2 server push is allowed for this resource.
+    ///
+    /// Push-capable servers and CDNs treat a preload link as push intent unless it carries
+    /// the `nopush` attribute, so `false` (the default) emits `nopush` while `true` leaves
+    /// the hint eligible for push.
+    pub push: bool,
+}
+
+impl PreloadLink {
+    /// Creates a preload link for `href` with the given `as` destination and push disabled.
+    pub fn new(href: impl Into<String>, destination: impl Into<String>) -> Self {
+        Self {
+            href: href.into(),
+            destination: destination.into(),
+            push: false,
+        }
+    }
+
+    /// Formats the link as a single `Link` header entry.
+    pub(crate) fn header_entry(&self) -> String {
+        let mut entry = format!("<{}>; rel=preload; as={}", self.href, self.destination);
+        if !self.push {
+            entry.push_str("; nopush");
+        }
+        entry
+    }
+}
+
+/// Joins preload links into a single `Link` header value.
+///
+/// Returns `None` when there are no links or the result is not a valid header value.
+pub(crate) fn link_header(links: &[PreloadLink]) -> Option<HeaderValue> {
+    if links.is_empty() {
+        return None;
+    }
+
+    let value = links
+        .iter()
+        .map(PreloadLink::header_entry)
+        .collect::<Vec<_>>()
+        .join(", ");
+    HeaderValue::from_str(&value).ok()
+}
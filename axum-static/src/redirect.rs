@@ -0,0 +1,125 @@
+//! Redirects issued before static files are served.
+
+use axum::{
+    body::Body,
+    extract::OriginalUri,
+    http::{HeaderValue, Request, StatusCode, Uri, header, uri::Authority},
+    response::{IntoResponse, Response},
+};
+use std::io;
+
+/// Returns the path and query the client originally requested.
+///
+/// Nested routers see a URI with the mount prefix stripped, so redirects are built
+/// from the [`OriginalUri`] when axum provides one.
+pub(crate) fn original_path_and_query(request: &Request<Body>) -> String {
+    let uri = original_uri(request);
+    uri.path_and_query()
+        .map_or_else(|| uri.path().to_owned(), ToString::to_string)
+}
+
+/// Returns the URI the client originally requested, before any mount prefix was stripped.
+fn original_uri(request: &Request<Body>) -> &Uri {
+    request
+        .extensions()
+        .get::<OriginalUri>()
+        .map_or(request.uri(), |original| &original.0)
+}
+
+/// Returns the redirect appending `/` to a request for a directory, keeping the query.
+///
+/// `ServeDir` issues the same redirect, but from the URI it sees, which loses the mount
+/// prefix of nested routers.
+pub(crate) fn directory(request: &Request<Body>) -> Response {
+    let uri = original_uri(request);
+    let location = match uri.query() {
+        Some(query) => format!("{}/?{query}", uri.path()),
+        None => format!("{}/", uri.path()),
+    };
+    to(StatusCode::TEMPORARY_REDIRECT, &location)
+}
+
+/// Returns whether the request is for the bare prefix of a nested router, such as
+/// `/static` for files mounted there, which the nested router sees as `/`.
+pub(crate) fn is_bare_prefix(request: &Request<Body>) -> bool {
+    request.uri().path() == "/" && !original_uri(request).path().ends_with('/')
+}
+
+/// Creates a redirect response pointing at `location`.
+pub(crate) fn to(status: StatusCode, location: &str) -> Response {
+    match HeaderValue::from_str(location) {
+        Ok(location) => (status, [(header::LOCATION, location)]).into_response(),
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+/// Returns whether the request's `Host` differs from `canonical`, ignoring case.
+pub(crate) fn is_off_host(request: &Request<Body>, canonical: &str) -> bool {
+    request
+        .headers()
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok())
+        .or_else(|| request.uri().authority().map(Authority::as_str))
+        .is_some_and(|host| !host.eq_ignore_ascii_case(canonical))
+}
+
+/// Returns the path of `location` when it is a path on this origin, such as `/new`
+/// for `/new?page=2`, and `None` for absolute and network-path references.
+pub(crate) fn local_path(location: &str) -> Option<&str> {
+    if !location.starts_with('/') || location.starts_with("//") {
+        return None;
+    }
+    location.split(['?', '#']).next()
+}
+
+/// A redirect or rewrite rule loaded from a redirects file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Rule {
+    pub(crate) from: String,
+    pub(crate) to: String,
+    pub(crate) status: StatusCode,
+}
+
+/// Parses a redirects file with one `from to [status]` rule per line.
+///
+/// Blank lines and lines starting with `#` are ignored. The status defaults to `301`
+/// and must be `200` (an internal rewrite) or a redirect status.
+pub(crate) fn parse_rules(contents: &str) -> io::Result<Vec<Rule>> {
+    let mut rules = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let invalid = |reason: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("redirects line {}: {reason}", number + 1),
+            )
+        };
+        let mut fields = line.split_whitespace();
+        let (Some(from), Some(to)) = (fields.next(), fields.next()) else {
+            return Err(invalid("expected `from to [status]`"));
+        };
+        let status = match fields.next() {
+            Some(status) => status
+                .parse::<StatusCode>()
+                .map_err(|_| invalid("invalid status code"))?,
+            None => StatusCode::MOVED_PERMANENTLY,
+        };
+        if status != StatusCode::OK && !status.is_redirection() {
+            return Err(invalid("status must be 200 or a redirect"));
+        }
+        if fields.next().is_some() {
+            return Err(invalid("unexpected trailing fields"));
+        }
+
+        rules.push(Rule {
+            from: from.to_owned(),
+            to: to.to_owned(),
+            status,
+        });
+    }
+    Ok(rules)
+}
@@ -0,0 +1,293 @@
+//! Ordered chains of content-type resolvers.
+//!
+//! A chain set with [`StaticRouter::content_type_resolvers`](crate::StaticRouter::content_type_resolvers)
+//! is consulted in order for every successful response, and the first resolver
+//! returning a media type labels it. This module holds the [`Resolver`] trait and the
+//! resolvers behind the router's own inference, to be combined with custom ones.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use axum_static::StaticRouter;
+//! use axum_static::resolver::{Builtin, ExtensionMap, Fixed, Lookup, PathMap, Sniff};
+//!
+//! let app = StaticRouter::new("static/")
+//!     .content_type_resolvers(vec![
+//!         Box::new(PathMap::new([("/feed", "application/atom+xml")])),
+//!         Box::new(|lookup: &Lookup<'_>| {
+//!             lookup
+//!                 .path()
+//!                 .starts_with("/raw/")
+//!                 .then(|| "text/plain".to_owned())
+//!         }),
+//!         Box::new(ExtensionMap::new([("glb", "model/gltf-binary")])),
+//!         Box::new(Builtin),
+//!         Box::new(Sniff),
+//!         Box::new(Fixed::new("application/octet-stream")),
+//!     ])
+//!     .build();
+//! ```
+
+use axum::http::HeaderValue;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::{fs, infer_content_type_from_extension, path_extension};
+
+/// Bytes read from the start of a file by [`Sniff`].
+const SNIFF_LENGTH: u64 = 512;
+
+/// The served file a [`Resolver`] is asked to label.
+#[derive(Debug)]
+pub struct Lookup<'a> {
+    path: &'a str,
+    extension: Option<&'a str>,
+    file: Option<&'a Path>,
+}
+
+impl Lookup<'_> {
+    /// Returns the request path of the served file, after rewrites and index
+    /// resolution, such as `/docs/index.html`. It is still percent-encoded.
+    #[must_use]
+    pub fn path(&self) -> &str {
+        self.path
+    }
+
+    /// Returns the lowercased extension of the served file without the leading dot,
+    /// if it has one.
+    #[must_use]
+    pub fn extension(&self) -> Option<&str> {
+        self.extension
+    }
+
+    /// Returns where the served file is on disk, or `None` if the path doesn't name a
+    /// file inside the root.
+    #[must_use]
+    pub fn file(&self) -> Option<&Path> {
+        self.file
+    }
+}
+
+/// Determines the media type of a served file, or passes to the next resolver.
+///
+/// Resolvers run on Tokio's blocking thread pool, so they may read the file. A
+/// returned type that is not a valid header value is skipped like `None`. Closures
+/// taking a [`Lookup`] are resolvers too.
+pub trait Resolver: Send + Sync + 'static {
+    /// Returns the media type of the file, such as `text/css`, or `None` to defer to
+    /// the next resolver.
+    fn resolve(&self, lookup: &Lookup<'_>) -> Option<String>;
+}
+
+impl<F> Resolver for F
+where
+    F: Fn(&Lookup<'_>) -> Option<String> + Send + Sync + 'static,
+{
+    fn resolve(&self, lookup: &Lookup<'_>) -> Option<String> {
+        self(lookup)
+    }
+}
+
+/// Labels files at exact request paths, such as `/feed`.
+#[derive(Debug, Clone, Default)]
+pub struct PathMap(HashMap<String, String>);
+
+impl PathMap {
+    /// Creates a map from pairs of request paths and media types.
+    #[must_use]
+    pub fn new<I, P, M>(paths: I) -> Self
+    where
+        I: IntoIterator<Item = (P, M)>,
+        P: Into<String>,
+        M: Into<String>,
+    {
+        Self(
+            paths
+                .into_iter()
+                .map(|(path, mime)| (path.into(), mime.into()))
+                .collect(),
+        )
+    }
+}
+
+impl Resolver for PathMap {
+    fn resolve(&self, lookup: &Lookup<'_>) -> Option<String> {
+        self.0.get(lookup.path).cloned()
+    }
+}
+
+/// Labels files by extension, such as `glb` as `model/gltf-binary`.
+#[derive(Debug, Clone, Default)]
+pub struct ExtensionMap(HashMap<String, String>);
+
+impl ExtensionMap {
+    /// Creates a map from pairs of extensions without the leading dot and media types.
+    #[must_use]
+    pub fn new<I, E, M>(extensions: I) -> Self
+    where
+        I: IntoIterator<Item = (E, M)>,
+        E: AsRef<str>,
+        M: Into<String>,
+    {
+        Self(
+            extensions
+                .into_iter()
+                .map(|(extension, mime)| (extension.as_ref().to_ascii_lowercase(), mime.into()))
+                .collect(),
+        )
+    }
+}
+
+impl Resolver for ExtensionMap {
+    fn resolve(&self, lookup: &Lookup<'_>) -> Option<String> {
+        self.0.get(lookup.extension?).cloned()
+    }
+}
+
+/// Labels files by the first line of a `<file>.meta` sidecar next to them, as with
+/// [`StaticRouter::sidecar_mime`](crate::StaticRouter::sidecar_mime).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sidecar;
+
+impl Resolver for Sidecar {
+    fn resolve(&self, lookup: &Lookup<'_>) -> Option<String> {
+        let mut path = lookup.file?.as_os_str().to_owned();
+        path.push(".meta");
+        let metadata = std::fs::metadata(&path).ok()?;
+        if !metadata.is_file() || metadata.len() > 1024 {
+            return None;
+        }
+
+        let contents = std::fs::read_to_string(path).ok()?;
+        let mime = contents.lines().next()?.trim();
+        (!mime.is_empty()).then(|| mime.to_owned())
+    }
+}
+
+/// Labels files by extension using the `mime_guess` crate.
+///
+/// # Features
+///
+/// This type is only available when the `mime_guess` feature is enabled.
+#[cfg(feature = "mime_guess")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MimeGuess;
+
+#[cfg(feature = "mime_guess")]
+impl Resolver for MimeGuess {
+    fn resolve(&self, lookup: &Lookup<'_>) -> Option<String> {
+        mime_guess::from_ext(lookup.extension?)
+            .first_raw()
+            .map(str::to_owned)
+    }
+}
+
+/// Labels files by extension using the crate's built-in table of common types.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Builtin;
+
+impl Resolver for Builtin {
+    fn resolve(&self, lookup: &Lookup<'_>) -> Option<String> {
+        match infer_content_type_from_extension(lookup.extension?) {
+            "application/octet-stream" => None,
+            mime => Some(mime.to_owned()),
+        }
+    }
+}
+
+/// Labels files by the signature in their first bytes, for common image, document,
+/// archive and web formats.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sniff;
+
+impl Resolver for Sniff {
+    fn resolve(&self, lookup: &Lookup<'_>) -> Option<String> {
+        let file = std::fs::File::open(lookup.file?).ok()?;
+        let mut head = Vec::new();
+        file.take(SNIFF_LENGTH).read_to_end(&mut head).ok()?;
+        sniff(&head).map(str::to_owned)
+    }
+}
+
+/// Labels every file with the same media type, usually last in a chain.
+#[derive(Debug, Clone)]
+pub struct Fixed(String);
+
+impl Fixed {
+    /// Creates a resolver answering `mime` for every file.
+    #[must_use]
+    pub fn new(mime: impl Into<String>) -> Self {
+        Self(mime.into())
+    }
+}
+
+impl Resolver for Fixed {
+    fn resolve(&self, _lookup: &Lookup<'_>) -> Option<String> {
+        Some(self.0.clone())
+    }
+}
+
+/// Returns the media type of the file at the request path from the first resolver in
+/// `resolvers` that names a valid one.
+pub(crate) async fn resolve(
+    resolvers: Arc<[Box<dyn Resolver>]>,
+    root: &Path,
+    path: &str,
+) -> Option<HeaderValue> {
+    let file = fs::resolve(root, path);
+    let path = path.to_owned();
+    tokio::task::spawn_blocking(move || {
+        let extension = path_extension(&path);
+        let lookup = Lookup {
+            path: &path,
+            extension: extension.as_deref(),
+            file: file.as_deref(),
+        };
+        resolvers
+            .iter()
+            .filter_map(|resolver| resolver.resolve(&lookup))
+            .find_map(|mime| HeaderValue::try_from(mime).ok())
+    })
+    .await
+    .ok()
+    .flatten()
+}
+
+/// Returns the media type announced by the signature at the start of a file.
+fn sniff(head: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: [(&[u8], &str); 9] = [
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"\0asm", "application/wasm"),
+        (b"wOF2", "font/woff2"),
+    ];
+    if let Some((_, mime)) = SIGNATURES
+        .iter()
+        .find(|(signature, _)| head.starts_with(signature))
+    {
+        return Some(mime);
+    }
+    if head.len() >= 12 && head.starts_with(b"RIFF") && &head[8..12] == b"WEBP" {
+        return Some("image/webp");
+    }
+
+    let text = head.trim_ascii_start();
+    let starts_with = |prefix: &[u8]| {
+        text.get(..prefix.len())
+            .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+    };
+    if starts_with(b"<!doctype html") || starts_with(b"<html") {
+        Some("text/html")
+    } else if starts_with(b"<?xml") {
+        Some("application/xml")
+    } else {
+        None
+    }
+}
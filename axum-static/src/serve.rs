@@ -0,0 +1,283 @@
+//! Serving files from disk through `ServeDir`.
+
+use axum::{
+    body::Body,
+    http::{HeaderValue, Request, StatusCode, header, request::Parts},
+    response::{IntoResponse, Response},
+};
+#[cfg(all(feature = "handle_error", feature = "status_code"))]
+use status_code::statuses;
+use std::io;
+use std::path::Path;
+use tower_http::services::ServeDir;
+#[cfg(feature = "tracing")]
+use tracing::debug;
+#[cfg(all(feature = "handle_error", feature = "tracing"))]
+use tracing::error;
+
+use crate::disconnect;
+use crate::options::{Callback, OnDisconnect, Options};
+use crate::source::{self, AsyncFileSource};
+
+/// The directories files are served from.
+#[derive(Debug, Clone)]
+pub(crate) struct Files {
+    primary: ServeDir,
+    /// A custom source replacing the primary directory.
+    source: Option<Callback<dyn AsyncFileSource>>,
+    failover: Option<ServeDir>,
+    /// Assets served when a file is missing from, or unreadable in, the roots.
+    fallback: Option<Callback<dyn AsyncFileSource>>,
+    retries: u32,
+    json_errors: bool,
+    on_disconnect: Option<Callback<OnDisconnect>>,
+}
+
+impl Files {
+    /// Creates the file services for the configured roots.
+    pub(crate) fn new(options: &Options) -> Self {
+        let serve_dir = |root: &Path| {
+            let mut serve_dir = ServeDir::new(root).append_index_html_on_directories(true);
+            if options.precompressed_gzip {
+                serve_dir = serve_dir.precompressed_gzip();
+            }
+            if options.precompressed_br {
+                serve_dir = serve_dir.precompressed_br();
+            }
+            if options.precompressed_zstd {
+                serve_dir = serve_dir.precompressed_zstd();
+            }
+            serve_dir
+        };
+
+        Self {
+            primary: serve_dir(&options.root),
+            source: options.file_source.clone(),
+            failover: options.failover_root.as_deref().map(serve_dir),
+            fallback: options.embedded_fallback.clone(),
+            retries: options.read_retries,
+            json_errors: options.json_errors,
+            on_disconnect: options.on_disconnect.clone(),
+        }
+    }
+
+    /// Serves the request from the primary root or custom source, then from the
+    /// failover root if the primary fails with an IO error, and finally from the
+    /// embedded fallback if the file is still missing or unreadable.
+    ///
+    /// IO errors from a dropped connection, such as a broken pipe, are expected when
+    /// clients go away and are answered quietly, without the `handle_error` handler.
+    /// Only errors raised while the file is opened are seen here; writes to the
+    /// connection fail in the server, which just drops the body.
+    ///
+    /// `ServeDir` ignores request bodies, so each attempt is made with an empty body.
+    pub(crate) async fn serve(self, request: Request<Body>) -> Response {
+        let (parts, _) = request.into_parts();
+        let result = self.serve_roots(&parts).await;
+
+        let missing = match &result {
+            Ok(response) => response.status() == StatusCode::NOT_FOUND,
+            Err(_) => true,
+        };
+        if missing
+            && let Some(fallback) = &self.fallback
+            && let Ok(response) = source::respond(&*fallback.0, &parts).await
+            && response.status() != StatusCode::NOT_FOUND
+        {
+            return response;
+        }
+
+        match result {
+            Ok(response) => response,
+            Err(err) if disconnect::is_disconnect(&err) => self.disconnected(&parts, &err),
+            Err(err) if self.json_errors => json_error(&err),
+            Err(err) => io_error(&err),
+        }
+    }
+
+    /// Returns the empty response for a request whose connection went away while its
+    /// file was opened, reporting it to the `on_disconnect` callback with no bytes sent.
+    fn disconnected(&self, parts: &Parts, err: &io::Error) -> Response {
+        #[cfg(feature = "tracing")]
+        debug!(path = %parts.uri.path(), error = %err, "client disconnected");
+        #[cfg(not(feature = "tracing"))]
+        let _ = err;
+
+        if let Some(callback) = &self.on_disconnect {
+            (callback.0)(parts.uri.path(), 0);
+        }
+        disconnect::client_closed().into_response()
+    }
+
+    /// Serves the request from the primary root or custom source, then from the
+    /// failover root if the primary fails with an IO error.
+    async fn serve_roots(&self, parts: &Parts) -> io::Result<Response> {
+        let primary = match &self.source {
+            Some(source) => attempt_source(&*source.0, parts, self.retries).await,
+            None => attempt(self.primary.clone(), parts, self.retries).await,
+        };
+        match (primary, &self.failover) {
+            (Err(_), Some(failover)) => attempt(failover.clone(), parts, self.retries).await,
+            (primary, _) => primary,
+        }
+    }
+}
+
+/// Serves the request from `serve_dir`, retrying like [`retrying`].
+async fn attempt(serve_dir: ServeDir, parts: &Parts, retries: u32) -> io::Result<Response> {
+    retrying(retries, || {
+        let mut serve_dir = serve_dir.clone();
+        let request = Request::from_parts(parts.clone(), Body::empty());
+        async move {
+            let response = serve_dir.try_call(request).await?;
+            Ok(response.map(Body::new))
+        }
+    })
+    .await
+}
+
+/// Serves the request from `source`, retrying like [`retrying`].
+async fn attempt_source(
+    source: &dyn AsyncFileSource,
+    parts: &Parts,
+    retries: u32,
+) -> io::Result<Response> {
+    retrying(retries, || source::respond(source, parts)).await
+}
+
+/// Runs `operation`, running it again up to `retries` times while it fails with a
+/// transient IO error.
+async fn retrying<T, F, Fut>(retries: u32, mut operation: F) -> io::Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = io::Result<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        match operation().await {
+            Err(err) if attempt < retries && is_transient(&err) => attempt += 1,
+            result => return result,
+        }
+    }
+}
+
+/// Returns whether the IO error may succeed when the operation is retried.
+fn is_transient(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::Interrupted
+            | io::ErrorKind::WouldBlock
+            | io::ErrorKind::TimedOut
+            | io::ErrorKind::ResourceBusy
+    )
+}
+
+/// Returns the response for an IO error that persisted through all retries.
+///
+/// Without the `handle_error` feature this is an empty 500 response, as `ServeDir`
+/// returns itself.
+fn io_error(err: &io::Error) -> Response {
+    #[cfg(feature = "handle_error")]
+    return handle_error(err).into_response();
+
+    #[cfg(not(feature = "handle_error"))]
+    {
+        let _ = err;
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    }
+}
+
+/// Returns the JSON response for an IO error, naming its kind but not its message.
+fn json_error(err: &io::Error) -> Response {
+    let status = StatusCode::INTERNAL_SERVER_ERROR;
+    let body = format!(
+        r#"{{"status":{},"error":"{}","kind":"{:?}"}}"#,
+        status.as_u16(),
+        status.canonical_reason().unwrap_or_default(),
+        err.kind()
+    );
+    (
+        status,
+        [(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        )],
+        body,
+    )
+        .into_response()
+}
+
+/// Error handler for IO errors when serving static files.
+///
+/// This function returns a 500 Internal Server Error response with the error message.
+///
+/// # Arguments
+///
+/// * `err` - The IO error that occurred.
+///
+/// # Features
+///
+/// This function is only available when the `handle_error` feature is enabled.
+#[cfg(feature = "handle_error")]
+fn handle_error(err: &io::Error) -> impl IntoResponse {
+    #[cfg(feature = "status_code")]
+    let (status, body) = {
+        let status = StatusCode::INTERNAL_SERVER_ERROR;
+        let code = status.as_u16();
+        let label = statuses::code(code);
+        let body = format!("static router IO error ({code} {label}): {err:?}");
+        (status, body)
+    };
+
+    #[cfg(not(feature = "status_code"))]
+    let (status, body) = {
+        let status = StatusCode::INTERNAL_SERVER_ERROR;
+        let body = format!("static router IO error: {err:?}");
+        (status, body)
+    };
+
+    #[cfg(feature = "tracing")]
+    {
+        error!(%body, error = %err, "static router IO error");
+    }
+
+    (status, body).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::retrying;
+    use std::cell::Cell;
+    use std::io;
+
+    /// Runs an operation failing `failures` times with `kind` under `retries`,
+    /// returning its result and how often it ran.
+    async fn run(failures: u32, kind: io::ErrorKind, retries: u32) -> (io::Result<()>, u32) {
+        let runs = Cell::new(0);
+        let result = retrying(retries, || {
+            runs.set(runs.get() + 1);
+            let failed = runs.get() <= failures;
+            async move { if failed { Err(kind.into()) } else { Ok(()) } }
+        })
+        .await;
+        (result, runs.get())
+    }
+
+    #[tokio::test]
+    async fn transient_errors_are_retried() {
+        let (result, runs) = run(2, io::ErrorKind::Interrupted, 2).await;
+        assert!(result.is_ok());
+        assert_eq!(runs, 3);
+
+        let (result, runs) = run(2, io::ErrorKind::TimedOut, 1).await;
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::TimedOut);
+        assert_eq!(runs, 2);
+    }
+
+    #[tokio::test]
+    async fn other_errors_are_returned_at_once() {
+        let (result, runs) = run(1, io::ErrorKind::PermissionDenied, 3).await;
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::PermissionDenied);
+        assert_eq!(runs, 1);
+    }
+}
@@ -0,0 +1,216 @@
+//! Custom sources of served files, such as object stores.
+
+use axum::{
+    body::Body,
+    http::{HeaderMap, HeaderValue, Method, StatusCode, header, request::Parts},
+    response::{IntoResponse, Response},
+};
+use std::collections::HashMap;
+use std::future::Future;
+use std::io;
+use std::path::{Component, Path};
+use std::pin::Pin;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::fs;
+
+/// The future returned by the methods of an [`AsyncFileSource`].
+pub type SourceFuture<'a, T> = Pin<Box<dyn Future<Output = io::Result<T>> + Send + 'a>>;
+
+/// What an [`AsyncFileSource`] knows about a file without reading it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStat {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl FileStat {
+    /// Describes a file of `len` bytes with an unknown modification time.
+    #[must_use]
+    pub fn new(len: u64) -> Self {
+        Self {
+            len,
+            modified: None,
+        }
+    }
+
+    /// Sets when the file was last modified, sent as `Last-Modified` and compared
+    /// with `If-Modified-Since`.
+    #[must_use]
+    pub fn modified(mut self, modified: SystemTime) -> Self {
+        self.modified = Some(modified);
+        self
+    }
+}
+
+/// A place files are served from instead of the local directory, such as an S3
+/// bucket.
+///
+/// Paths are relative to the source, percent-decoded and free of `.` and `..`
+/// segments, such as `docs/index.html` for a request to `/docs/`; requests that
+/// would climb above the root never reach the source. IO errors are retried and
+/// failed over like those of the local directory.
+///
+/// ## Example
+///
+/// ```rust
+/// use axum::body::Body;
+/// use axum_static::{AsyncFileSource, FileStat, SourceFuture, StaticRouter};
+/// use std::collections::HashMap;
+///
+/// struct Objects(HashMap<String, &'static str>);
+///
+/// impl AsyncFileSource for Objects {
+///     fn stat<'a>(&'a self, path: &'a str) -> SourceFuture<'a, Option<FileStat>> {
+///         Box::pin(async move {
+///             Ok(self.0.get(path).map(|object| FileStat::new(object.len() as u64)))
+///         })
+///     }
+///
+///     fn open<'a>(&'a self, path: &'a str) -> SourceFuture<'a, Body> {
+///         Box::pin(async move { Ok(Body::from(self.0[path])) })
+///     }
+/// }
+///
+/// let objects = Objects(HashMap::from([("app.css".to_owned(), "body {}")]));
+/// let app = StaticRouter::new("static/").file_source(objects).build();
+/// ```
+pub trait AsyncFileSource: Send + Sync + 'static {
+    /// Returns the stat of the file at `path`, or `None` if there is no such file.
+    fn stat<'a>(&'a self, path: &'a str) -> SourceFuture<'a, Option<FileStat>>;
+
+    /// Opens the file at `path` as a body streaming its contents, such as with
+    /// [`Body::from_stream`].
+    fn open<'a>(&'a self, path: &'a str) -> SourceFuture<'a, Body>;
+}
+
+/// Answers a `GET` or `HEAD` request from `source`.
+///
+/// `If-Modified-Since` is answered with `304 Not Modified` for files with a known
+/// modification time. Ranges aren't supported, so the whole file is always sent.
+pub(crate) async fn respond(source: &dyn AsyncFileSource, parts: &Parts) -> io::Result<Response> {
+    if parts.method != Method::GET && parts.method != Method::HEAD {
+        let mut response = StatusCode::METHOD_NOT_ALLOWED.into_response();
+        response
+            .headers_mut()
+            .insert(header::ALLOW, HeaderValue::from_static("GET,HEAD"));
+        return Ok(response);
+    }
+
+    let Some(path) = source_path(parts.uri.path()) else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+    let Some(stat) = source.stat(&path).await? else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+
+    let last_modified = stat
+        .modified
+        .map(|modified| HeaderValue::try_from(httpdate::fmt_http_date(modified)));
+    if let Some(modified) = stat.modified
+        && not_modified(&parts.headers, modified)
+    {
+        let mut response = StatusCode::NOT_MODIFIED.into_response();
+        if let Some(Ok(value)) = last_modified {
+            response.headers_mut().insert(header::LAST_MODIFIED, value);
+        }
+        return Ok(response);
+    }
+
+    let body = if parts.method == Method::HEAD {
+        Body::empty()
+    } else {
+        source.open(&path).await?
+    };
+    let mut response = Response::new(body);
+    let headers = response.headers_mut();
+    headers.insert(header::CONTENT_LENGTH, HeaderValue::from(stat.len));
+    if let Some(Ok(value)) = last_modified {
+        headers.insert(header::LAST_MODIFIED, value);
+    }
+    Ok(response)
+}
+
+/// Returns the source path of the request path, or `None` if it leaves the root.
+pub(crate) fn source_path(request_path: &str) -> Option<String> {
+    let path = fs::resolve(Path::new(""), request_path)?;
+    let segments = path
+        .components()
+        .map(|component| match component {
+            Component::Normal(segment) => segment.to_str(),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(segments.join("/"))
+}
+
+/// Returns whether `If-Modified-Since` shows the client's copy is current.
+///
+/// As with `ServeDir`, the condition is ignored when `If-None-Match` is present.
+fn not_modified(headers: &HeaderMap, modified: SystemTime) -> bool {
+    if headers.contains_key(header::IF_NONE_MATCH) {
+        return false;
+    }
+    let Some(since) = headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| httpdate::parse_http_date(value).ok())
+    else {
+        return false;
+    };
+    let seconds = |time: SystemTime| {
+        time.duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs())
+    };
+    seconds(modified) <= seconds(since)
+}
+
+/// Files compiled into the binary, such as with `include_bytes!`, served as an
+/// [`AsyncFileSource`].
+///
+/// ## Example
+///
+/// ```rust
+/// use axum_static::{EmbeddedAssets, StaticRouter};
+///
+/// let assets = EmbeddedAssets::new()
+///     .file("404.html", b"<h1>Not found</h1>")
+///     .file("maintenance.html", b"<h1>Back soon</h1>");
+/// let app = StaticRouter::new("static/").embedded_fallback(assets).build();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EmbeddedAssets {
+    files: HashMap<String, &'static [u8]>,
+}
+
+impl EmbeddedAssets {
+    /// Creates an empty set of assets.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a file at `path`, relative to the root with or without a leading `/`,
+    /// such as `errors/404.html`.
+    #[must_use]
+    pub fn file(mut self, path: &str, contents: &'static [u8]) -> Self {
+        self.files
+            .insert(path.trim_start_matches('/').to_owned(), contents);
+        self
+    }
+}
+
+impl AsyncFileSource for EmbeddedAssets {
+    fn stat<'a>(&'a self, path: &'a str) -> SourceFuture<'a, Option<FileStat>> {
+        let stat = self
+            .files
+            .get(path)
+            .map(|contents| FileStat::new(contents.len() as u64));
+        Box::pin(async move { Ok(stat) })
+    }
+
+    fn open<'a>(&'a self, path: &'a str) -> SourceFuture<'a, Body> {
+        let body = self.files.get(path).map(|contents| Body::from(*contents));
+        Box::pin(async move { body.ok_or_else(|| io::ErrorKind::NotFound.into()) })
+    }
+}
@@ -0,0 +1,63 @@
+//! Diagnostic JSON describing the configured router.
+
+use std::fmt::Write;
+use std::path::Path;
+
+/// Pairs each named cargo feature with whether it is enabled.
+macro_rules! features {
+    ($($name:literal),* $(,)?) => {
+        [$(($name, cfg!(feature = $name))),*]
+    };
+}
+
+/// Every cargo feature of the crate, in the order they are reported.
+const FEATURES: [(&str, bool); 7] = features![
+    "cdn_fallback",
+    "compression",
+    "handle_error",
+    "mime_guess",
+    "status_code",
+    "tracing",
+    "unicode_normalize",
+];
+
+/// Returns the status document for a router serving `root`.
+///
+/// The document has the form
+/// `{"version":"1.8.6","root":"static/","features":{"cdn_fallback":false,...}}`.
+pub(crate) fn document(root: &Path) -> String {
+    let mut json = format!(
+        "{{\"version\":{},\"root\":{},\"features\":{{",
+        string(env!("CARGO_PKG_VERSION")),
+        string(&root.to_string_lossy()),
+    );
+    for (index, (name, enabled)) in FEATURES.iter().enumerate() {
+        if index > 0 {
+            json.push(',');
+        }
+        let _ = write!(json, "{}:{enabled}", string(name));
+    }
+    json.push_str("}}");
+    json
+}
+
+/// Formats `value` as a JSON string literal.
+fn string(value: &str) -> String {
+    let mut literal = String::with_capacity(value.len() + 2);
+    literal.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(literal, "\\u{:04x}", u32::from(c));
+            }
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
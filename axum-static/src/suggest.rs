@@ -0,0 +1,108 @@
+//! Suggestions of similarly named files for requests that weren't found.
+
+use percent_encoding::{AsciiSet, CONTROLS, utf8_percent_encode};
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::fs;
+
+/// Directory entries examined per request, bounding the cost of a lookup.
+const MAX_ENTRIES: usize = 1000;
+
+/// Suggestions listed per response.
+const MAX_SUGGESTIONS: usize = 5;
+
+/// Characters percent-encoded in the suggested links.
+const SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?');
+
+/// Returns an HTML page linking to files next to the request path with similar names.
+///
+/// Names are similar when one starts with the other or they are within a couple of
+/// edits of each other, ignoring case. Returns `None` when nothing is similar.
+pub(crate) async fn page(root: &Path, request_path: &str) -> Option<String> {
+    let (directory, requested) = request_path.rsplit_once('/')?;
+    if requested.is_empty() {
+        return None;
+    }
+    let requested = requested.to_lowercase();
+
+    let mut entries = tokio::fs::read_dir(fs::resolve(root, directory)?)
+        .await
+        .ok()?;
+    let mut similar = Vec::new();
+    for _ in 0..MAX_ENTRIES {
+        let Ok(Some(entry)) = entries.next_entry().await else {
+            break;
+        };
+        let Ok(name) = entry.file_name().into_string() else {
+            continue;
+        };
+        if let Some(distance) = similarity(&requested, &name.to_lowercase()) {
+            similar.push((distance, name));
+        }
+    }
+    if similar.is_empty() {
+        return None;
+    }
+    similar.sort();
+
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<title>Not Found</title>\n<h1>Not Found</h1>\n<p>Did you mean:</p>\n<ul>\n",
+    );
+    for (_, name) in similar.iter().take(MAX_SUGGESTIONS) {
+        let href = utf8_percent_encode(name, SEGMENT).to_string();
+        let _ = writeln!(
+            html,
+            "<li><a href=\"{}\">{}</a></li>",
+            escape(&href),
+            escape(name)
+        );
+    }
+    html.push_str("</ul>\n");
+    Some(html)
+}
+
+/// Returns how far `candidate` is from `requested`, if they are similar at all.
+fn similarity(requested: &str, candidate: &str) -> Option<usize> {
+    if requested == candidate {
+        return None;
+    }
+    if requested.len() >= 3
+        && (candidate.starts_with(requested) || requested.starts_with(candidate))
+    {
+        return Some(requested.len().abs_diff(candidate.len()));
+    }
+    let distance = edit_distance(requested, candidate);
+    (distance <= 2).then_some(distance)
+}
+
+/// Returns the Levenshtein distance between two strings, by characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Escapes text for inclusion in HTML content and attribute values.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
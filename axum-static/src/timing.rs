@@ -0,0 +1,40 @@
+//! `Server-Timing` metrics for the serving phases.
+
+use axum::http::HeaderValue;
+use std::fmt::Write;
+use std::time::Duration;
+
+/// Time spent in each serving phase; phases that didn't run are left out.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct ServerTiming {
+    /// Filesystem lookups made while resolving the served file.
+    pub(crate) stat: Option<Duration>,
+    /// Opening the file and producing the streamed response.
+    pub(crate) stream: Option<Duration>,
+    /// Content-type inference.
+    pub(crate) infer: Option<Duration>,
+}
+
+impl ServerTiming {
+    /// Formats the recorded phases as a `Server-Timing` header value.
+    pub(crate) fn header_value(&self) -> Option<HeaderValue> {
+        let mut value = String::new();
+        for (name, duration) in [
+            ("stat", self.stat),
+            ("stream", self.stream),
+            ("infer", self.infer),
+        ] {
+            let Some(duration) = duration else {
+                continue;
+            };
+            if !value.is_empty() {
+                value.push_str(", ");
+            }
+            let millis = duration.as_secs_f64() * 1000.0;
+            let _ = write!(value, "{name};dur={millis:.3}");
+        }
+        HeaderValue::from_str(&value)
+            .ok()
+            .filter(|_| !value.is_empty())
+    }
+}
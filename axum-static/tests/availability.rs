@@ -0,0 +1,152 @@
+//! Maintenance mode, connection limits and request deadlines.
+
+mod common;
+
+use axum::{Router, body::Body, extract::ConnectInfo, http::Response};
+use axum_static::StaticRouter;
+use common::{Site, get, get_with, header, send, text};
+use std::net::SocketAddr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[tokio::test]
+async fn retry_after_is_set_on_maintenance_responses() {
+    let site = Site::with(&[("index.html", "home")]);
+    let app = StaticRouter::new(site.path())
+        .maintenance_mode(true)
+        .retry_after(Duration::from_mins(2))
+        .build();
+
+    let response = get(&app, "/index.html").await;
+    assert_eq!(response.status(), 503);
+    assert_eq!(header(&response, "retry-after"), Some("120"));
+
+    let app = StaticRouter::new(site.path())
+        .retry_after(Duration::from_mins(2))
+        .build();
+    let response = get(&app, "/index.html").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "retry-after"), None);
+}
+
+#[tokio::test]
+async fn maintenance_allowlist_serves_listed_clients() {
+    let site = Site::with(&[("index.html", "home")]);
+    let app = StaticRouter::new(site.path())
+        .maintenance_mode(true)
+        .maintenance_allowlist(["10.0.0.0/8", "2001:db8::1"])
+        .build();
+
+    let response = get_from(&app, "/index.html", [10, 1, 2, 3]).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(text(response).await, "home");
+    let response = get_from(&app, "/index.html", [192, 168, 0, 1]).await;
+    assert_eq!(response.status(), 503);
+    let response = get(&app, "/index.html").await;
+    assert_eq!(
+        response.status(),
+        503,
+        "unidentified clients aren't allowed"
+    );
+
+    let app = StaticRouter::new(site.path())
+        .maintenance_mode(true)
+        .maintenance_allowlist(["2001:db8::1"])
+        .client_ip_header("x-forwarded-for")
+        .build();
+    let response = get_with(
+        &app,
+        "/",
+        &[("x-forwarded-for", "203.0.113.9, 2001:db8::1")],
+    )
+    .await;
+    assert_eq!(response.status(), 200);
+    let response = get_with(
+        &app,
+        "/",
+        &[("x-forwarded-for", "2001:db8::1, 203.0.113.9")],
+    )
+    .await;
+    assert_eq!(response.status(), 503);
+}
+
+/// Sends a `GET` for `uri` as if from a client connected from `ip`.
+async fn get_from(app: &Router, uri: &str, ip: [u8; 4]) -> Response<Body> {
+    let address = SocketAddr::from((ip, 40000));
+    let request = common::request(uri)
+        .extension(ConnectInfo(address))
+        .body(Body::empty())
+        .expect("request");
+    send(app, request).await
+}
+
+#[tokio::test]
+async fn max_connections_per_ip_limits_requests_in_flight() {
+    let site = Site::with(&[("app.js", "js")]);
+    let app = StaticRouter::new(site.path())
+        .max_connections_per_ip(2)
+        .build();
+
+    let first = get_from(&app, "/app.js", [10, 0, 0, 1]).await;
+    let second = get_from(&app, "/app.js", [10, 0, 0, 1]).await;
+    assert_eq!(first.status(), 200);
+    assert_eq!(second.status(), 200);
+    let response = get_from(&app, "/app.js", [10, 0, 0, 1]).await;
+    assert_eq!(response.status(), 429);
+
+    let response = get_from(&app, "/app.js", [10, 0, 0, 2]).await;
+    assert_eq!(response.status(), 200);
+    let response = get(&app, "/app.js").await;
+    assert_eq!(
+        response.status(),
+        200,
+        "unidentified clients aren't limited"
+    );
+
+    assert_eq!(text(first).await, "js");
+    let response = get_from(&app, "/app.js", [10, 0, 0, 1]).await;
+    assert_eq!(response.status(), 200);
+    drop(second);
+}
+
+#[tokio::test]
+async fn max_connections_per_ip_uses_the_trusted_header() {
+    let site = Site::with(&[("app.js", "js")]);
+    let app = StaticRouter::new(site.path())
+        .max_connections_per_ip(1)
+        .client_ip_header("x-forwarded-for")
+        .build();
+
+    let forwarded = |client| [("x-forwarded-for", client)];
+    let held = get_with(&app, "/app.js", &forwarded("203.0.113.9, 10.0.0.1")).await;
+    assert_eq!(held.status(), 200);
+    let response = get_with(&app, "/app.js", &forwarded("198.51.100.7, 10.0.0.1")).await;
+    assert_eq!(response.status(), 429);
+    let response = get_with(&app, "/app.js", &forwarded("10.0.0.1, 10.0.0.2")).await;
+    assert_eq!(response.status(), 200);
+    drop(held);
+}
+
+#[tokio::test]
+async fn deadline_header_times_out_late_requests() {
+    let site = Site::with(&[("index.html", "home")]);
+    let app = StaticRouter::new(site.path())
+        .deadline_header("x-request-deadline")
+        .build();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("after the epoch")
+        .as_secs_f64();
+    let deadline = |seconds: f64| format!("{seconds:.3}");
+
+    let response = get_with(&app, "/", &[("x-request-deadline", &deadline(now - 1.0))]).await;
+    assert_eq!(response.status(), 504);
+
+    let response = get_with(&app, "/", &[("x-request-deadline", &deadline(now + 60.0))]).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(text(response).await, "home");
+
+    for invalid in ["soon", "-1", ""] {
+        let response = get_with(&app, "/", &[("x-request-deadline", invalid)]).await;
+        assert_eq!(response.status(), 200, "{invalid:?}");
+    }
+}
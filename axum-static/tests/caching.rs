@@ -0,0 +1,206 @@
+//! `Cache-Control` and in-memory caching of served files.
+
+mod common;
+
+use axum_static::{CachePolicy, MemoryCache, StaticRouter, static_router_cached};
+use common::{Site, get, get_with, header, text};
+use http_body_util::BodyExt;
+use std::time::{Duration, SystemTime};
+
+#[tokio::test]
+async fn cache_by_age_caches_stable_files_longer() {
+    let site = Site::with(&[("fresh.css", "a {}"), ("stable.css", "b {}")]);
+    site.modified(
+        "stable.css",
+        SystemTime::now() - Duration::from_hours(24 * 30),
+    );
+    let app = StaticRouter::new(site.path())
+        .cache_by_age(|age| {
+            if age < Duration::from_hours(24) {
+                "public, max-age=60".to_owned()
+            } else {
+                "public, max-age=86400".to_owned()
+            }
+        })
+        .build();
+
+    let response = get(&app, "/fresh.css").await;
+    assert_eq!(
+        header(&response, "cache-control"),
+        Some("public, max-age=60")
+    );
+
+    let response = get(&app, "/stable.css").await;
+    assert_eq!(
+        header(&response, "cache-control"),
+        Some("public, max-age=86400")
+    );
+
+    let response = get(&app, "/missing.css").await;
+    assert_eq!(response.status(), 404);
+    assert_eq!(header(&response, "cache-control"), None);
+}
+
+#[tokio::test]
+async fn memory_cache_honors_extension_limits() {
+    let site = Site::with(&[("app.css", "a {}"), ("photo.png", "old!")]);
+    let modified = SystemTime::now() - Duration::from_hours(1);
+    site.modified("app.css", modified)
+        .modified("photo.png", modified);
+    let cache = MemoryCache::new(1024 * 1024)
+        .extension_limit("css", 1024)
+        .extension_limit("png", 0);
+    let app = StaticRouter::new(site.path()).memory_cache(cache).build();
+
+    assert_eq!(text(get(&app, "/app.css").await).await, "a {}");
+    assert_eq!(text(get(&app, "/photo.png").await).await, "old!");
+
+    // Same size and modification time, so only a cached copy still has the old bytes.
+    site.file("app.css", "b {}").modified("app.css", modified);
+    site.file("photo.png", "new!")
+        .modified("photo.png", modified);
+    assert_eq!(text(get(&app, "/app.css").await).await, "a {}");
+    assert_eq!(text(get(&app, "/photo.png").await).await, "new!");
+}
+
+#[tokio::test]
+async fn cache_jitter_stays_within_the_range() {
+    let site = Site::with(&[("app.js", "js"), ("page.html", "page")]);
+    let app = StaticRouter::new(site.path())
+        .cache_control_for_extension("js", "public, max-age=600, immutable")
+        .cache_control_for_extension("html", "no-cache")
+        .cache_jitter(Duration::from_mins(1))
+        .build();
+
+    let mut ages = std::collections::HashSet::new();
+    for _ in 0..50 {
+        let response = get(&app, "/app.js").await;
+        let value = header(&response, "cache-control").expect("Cache-Control");
+        let age = value
+            .strip_prefix("public, max-age=")
+            .and_then(|rest| rest.strip_suffix(", immutable"))
+            .and_then(|age| age.parse::<u64>().ok())
+            .unwrap_or_else(|| panic!("unexpected Cache-Control {value}"));
+        assert!((540..=660).contains(&age), "{age}");
+        ages.insert(age);
+    }
+    assert!(ages.len() > 1, "max-age never varied");
+
+    let response = get(&app, "/page.html").await;
+    assert_eq!(header(&response, "cache-control"), Some("no-cache"));
+}
+
+#[tokio::test]
+async fn static_router_cached_picks_cache_control_by_file_type() {
+    let site = Site::with(&[
+        ("app.8f3c21ab.js", "js"),
+        ("index-BQrd3x1a.js", "js"),
+        ("index.html", "home"),
+        ("logo.png", "png"),
+        ("data.json", "{}"),
+    ]);
+    let policy = CachePolicy::new(Duration::from_hours(1))
+        .extension("json", "no-store")
+        .immutable_fingerprints(true);
+    let app = static_router_cached(site.path(), policy);
+
+    for (path, value) in [
+        ("/app.8f3c21ab.js", "public, max-age=31536000, immutable"),
+        ("/index-BQrd3x1a.js", "public, max-age=31536000, immutable"),
+        ("/index.html", "no-cache"),
+        ("/", "no-cache"),
+        ("/logo.png", "public, max-age=3600"),
+        ("/data.json", "no-store"),
+    ] {
+        let response = get(&app, path).await;
+        assert_eq!(header(&response, "cache-control"), Some(value), "{path}");
+    }
+
+    let response = get(&app, "/logo.png").await;
+    let etag = header(&response, "etag").expect("ETag").to_owned();
+    let response = get_with(&app, "/logo.png", &[("if-none-match", &etag)]).await;
+    assert_eq!(response.status(), 304);
+}
+
+#[tokio::test]
+async fn dated_and_versioned_names_are_not_fingerprints() {
+    let names = [
+        "photo-20231201.jpg",
+        "report-2024q1.pdf",
+        "v1beta2.js",
+        "app.v1beta2.js",
+        "app.8f3c.js",
+        "release-candidate1.js",
+        "app.8f3c21ab.js",
+    ];
+    let site = Site::new();
+    for name in names {
+        site.file(name, "asset");
+    }
+    let policy = CachePolicy::new(Duration::from_hours(1)).immutable_fingerprints(true);
+    let app = static_router_cached(site.path(), policy);
+
+    for name in &names[..names.len() - 1] {
+        let response = get(&app, &format!("/{name}")).await;
+        assert_eq!(
+            header(&response, "cache-control"),
+            Some("public, max-age=3600"),
+            "{name}"
+        );
+    }
+
+    let app = static_router_cached(site.path(), CachePolicy::new(Duration::from_hours(1)));
+    let response = get(&app, "/app.8f3c21ab.js").await;
+    assert_eq!(
+        header(&response, "cache-control"),
+        Some("public, max-age=3600"),
+        "fingerprints are opt-in"
+    );
+}
+
+/// Returns the size of the first frame of `response`'s body.
+async fn first_frame_len(response: axum::http::Response<axum::body::Body>) -> usize {
+    response
+        .into_body()
+        .frame()
+        .await
+        .expect("a frame")
+        .expect("readable")
+        .into_data()
+        .expect("data frame")
+        .len()
+}
+
+#[tokio::test]
+async fn coalesced_requests_are_answered_from_one_read() {
+    const SIZE: usize = 4 << 20;
+    let site = Site::new();
+    site.file("bundle.js", vec![b'x'; SIZE]);
+    let app = StaticRouter::new(site.path())
+        .coalesce_requests(true)
+        .build();
+
+    // A lone request streams the file from disk in chunks.
+    let first = get(&app, "/bundle.js").await;
+    assert_eq!(first.status(), 200);
+
+    // Requests arriving while it is in flight share one read, sent whole.
+    let read = || async {
+        let response = get(&app, "/bundle.js").await;
+        assert_eq!(response.status(), 200);
+        assert_eq!(header(&response, "content-type"), Some("text/javascript"));
+        first_frame_len(response).await
+    };
+    let frames = tokio::join!(read(), read(), read());
+    assert_eq!(frames, (SIZE, SIZE, SIZE));
+    assert!(first_frame_len(first).await < SIZE);
+
+    site.file("bundle.js", "changed");
+    assert_eq!(text(get(&app, "/bundle.js").await).await, "changed");
+
+    site.file("bundle.js", vec![b'x'; SIZE]);
+    assert!(first_frame_len(get(&app, "/bundle.js").await).await < SIZE);
+    let response = get_with(&app, "/bundle.js", &[("range", "bytes=0-9")]).await;
+    assert_eq!(response.status(), 206);
+    assert_eq!(text(response).await, "x".repeat(10));
+}
@@ -0,0 +1,131 @@
+//! Helpers shared by the integration tests.
+
+#![allow(dead_code)]
+
+use axum::{
+    Router,
+    body::{Body, Bytes},
+    http::{HeaderName, Request, Response},
+};
+use http_body_util::BodyExt;
+use std::path::Path;
+use std::time::SystemTime;
+use tempfile::TempDir;
+use tower::ServiceExt;
+
+/// A temporary static directory, removed when dropped.
+pub struct Site {
+    dir: TempDir,
+}
+
+impl Site {
+    /// Creates an empty site.
+    pub fn new() -> Self {
+        Self {
+            dir: TempDir::new().expect("create temp dir"),
+        }
+    }
+
+    /// Creates a site holding `files`, pairs of paths relative to the root and
+    /// contents.
+    pub fn with(files: &[(&str, &str)]) -> Self {
+        let site = Self::new();
+        for (path, contents) in files {
+            site.file(path, contents);
+        }
+        site
+    }
+
+    /// Writes the file at `path`, relative to the root, creating its directories.
+    pub fn file(&self, path: &str, contents: impl AsRef<[u8]>) -> &Self {
+        let path = self.dir.path().join(path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).expect("create dirs");
+        }
+        std::fs::write(path, contents).expect("write file");
+        self
+    }
+
+    /// Sets the modification time of the file at `path`.
+    pub fn modified(&self, path: &str, time: SystemTime) -> &Self {
+        std::fs::File::options()
+            .write(true)
+            .open(self.dir.path().join(path))
+            .and_then(|file| file.set_modified(time))
+            .expect("set mtime");
+        self
+    }
+
+    /// Returns the root directory of the site.
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+}
+
+/// Sends `request` to `app`.
+pub async fn send(app: &Router, request: Request<Body>) -> Response<Body> {
+    app.clone().oneshot(request).await.expect("infallible")
+}
+
+/// Sends a `GET` for `uri` to `app`.
+pub async fn get(app: &Router, uri: &str) -> Response<Body> {
+    send(app, request(uri).body(Body::empty()).expect("request")).await
+}
+
+/// Sends a `GET` for `uri` with `headers` to `app`.
+pub async fn get_with(app: &Router, uri: &str, headers: &[(&str, &str)]) -> Response<Body> {
+    let mut builder = request(uri);
+    for (name, value) in headers {
+        builder = builder.header(*name, *value);
+    }
+    send(app, builder.body(Body::empty()).expect("request")).await
+}
+
+/// Starts a `GET` request for `uri`.
+pub fn request(uri: &str) -> axum::http::request::Builder {
+    Request::get(uri)
+}
+
+/// Returns the value of the `name` header, if present and visible ASCII.
+pub fn header<N>(response: &Response<Body>, name: N) -> Option<&str>
+where
+    N: TryInto<HeaderName>,
+{
+    let name = name.try_into().ok()?;
+    response.headers().get(name)?.to_str().ok()
+}
+
+/// Collects the body of `response`.
+pub async fn bytes(response: Response<Body>) -> Bytes {
+    response
+        .into_body()
+        .collect()
+        .await
+        .expect("body")
+        .to_bytes()
+}
+
+/// Collects the body of `response` as UTF-8 text.
+pub async fn text(response: Response<Body>) -> String {
+    String::from_utf8(bytes(response).await.to_vec()).expect("utf-8 body")
+}
+
+/// Encodes or decodes `data` with the content coding `coding`, `gzip` or `br`.
+#[cfg(feature = "compression")]
+pub async fn code(coding: &str, encode: bool, data: &[u8]) -> Vec<u8> {
+    use async_compression::tokio::bufread::{
+        BrotliDecoder, BrotliEncoder, GzipDecoder, GzipEncoder,
+    };
+    use tokio::io::{AsyncRead, AsyncReadExt};
+
+    let mut reader: Box<dyn AsyncRead + Unpin> = match (coding, encode) {
+        ("gzip", true) => Box::new(GzipEncoder::new(data)),
+        ("gzip", false) => Box::new(GzipDecoder::new(data)),
+        ("br", true) => Box::new(BrotliEncoder::new(data)),
+        ("br", false) => Box::new(BrotliDecoder::new(data)),
+        _ => panic!("unsupported coding {coding}"),
+    };
+    let mut out = Vec::new();
+    reader.read_to_end(&mut out).await.expect("coding");
+    out
+}
@@ -0,0 +1,174 @@
+//! Precompressed variants and on-the-fly compression.
+
+mod common;
+
+use axum_static::{Encodings, StaticRouter, static_router_precompressed};
+use common::{Site, get_with, header, text};
+use std::time::Duration;
+
+/// A text body long enough to be compressed on the fly.
+const TEXT: &str = "static files served with compression, repeated to pass the size threshold";
+
+#[tokio::test]
+async fn sourcemap_policy_sets_type_cache_and_compression() {
+    let site = Site::with(&[("app.js.map", TEXT)]);
+    let app = StaticRouter::new(site.path())
+        .sourcemap_policy(Duration::from_hours(24 * 365))
+        .build();
+
+    let response = get_with(&app, "/app.js.map", &[("accept-encoding", "gzip")]).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "content-type"), Some("application/json"));
+    assert_eq!(
+        header(&response, "cache-control"),
+        Some("public, max-age=31536000, immutable")
+    );
+
+    #[cfg(feature = "compression")]
+    {
+        assert_eq!(header(&response, "content-encoding"), Some("gzip"));
+        let body = common::bytes(response).await;
+        assert_eq!(common::code("gzip", false, &body).await, TEXT.as_bytes());
+    }
+}
+
+#[tokio::test]
+async fn refused_identity_is_not_acceptable_without_a_variant() {
+    let site = Site::with(&[("plain.js", TEXT), ("app.js.map", TEXT)]);
+    let app = StaticRouter::new(site.path())
+        .sourcemap_policy(Duration::from_hours(1))
+        .build();
+    let refuses_identity = [("accept-encoding", "identity;q=0, gzip")];
+
+    let response = get_with(&app, "/plain.js", &refuses_identity).await;
+    assert_eq!(response.status(), 406);
+
+    // Only source maps are compressed on the fly.
+    let response = get_with(&app, "/app.js.map", &refuses_identity).await;
+    #[cfg(feature = "compression")]
+    {
+        assert_eq!(response.status(), 200);
+        assert_eq!(header(&response, "content-encoding"), Some("gzip"));
+    }
+    #[cfg(not(feature = "compression"))]
+    assert_eq!(response.status(), 406);
+
+    let response = get_with(
+        &app,
+        "/plain.js",
+        &[("accept-encoding", "gzip;q=0.5, identity;q=0.1")],
+    )
+    .await;
+    assert_eq!(response.status(), 200);
+}
+
+#[cfg(feature = "compression")]
+#[tokio::test]
+async fn compress_brotli_encodes_only_for_br_clients() {
+    let site = Site::with(&[("app.css", TEXT)]);
+    let app = StaticRouter::new(site.path()).compress_brotli(true).build();
+
+    let response = get_with(&app, "/app.css", &[("accept-encoding", "gzip, br")]).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "content-type"), Some("text/css"));
+    assert_eq!(header(&response, "content-encoding"), Some("br"));
+    let body = common::bytes(response).await;
+    assert_eq!(common::code("br", false, &body).await, TEXT.as_bytes());
+
+    let response = get_with(&app, "/app.css", &[("accept-encoding", "gzip")]).await;
+    assert_eq!(header(&response, "content-encoding"), None);
+    assert_eq!(common::text(response).await, TEXT);
+}
+
+#[cfg(feature = "compression")]
+#[tokio::test]
+async fn precompressed_variant_wins_over_on_the_fly_compression() {
+    let stored = common::code("gzip", true, b"precompressed contents").await;
+    let site = Site::with(&[("app.js", TEXT), ("other.js", TEXT)]);
+    site.file("app.js.gz", &stored);
+    let app = StaticRouter::new(site.path())
+        .precompressed_gzip()
+        .compress(true)
+        .build();
+
+    let response = get_with(&app, "/app.js", &[("accept-encoding", "gzip")]).await;
+    assert_eq!(header(&response, "content-encoding"), Some("gzip"));
+    assert_eq!(header(&response, "content-type"), Some("text/javascript"));
+    assert_eq!(common::bytes(response).await, stored);
+
+    let response = get_with(&app, "/other.js", &[("accept-encoding", "gzip")]).await;
+    assert_eq!(header(&response, "content-encoding"), Some("gzip"));
+    let body = common::bytes(response).await;
+    assert_eq!(common::code("gzip", false, &body).await, TEXT.as_bytes());
+}
+
+#[cfg(feature = "compression")]
+#[tokio::test]
+async fn auto_decompress_decodes_brotli_only_files() {
+    let stored = common::code("br", true, TEXT.as_bytes()).await;
+    let site = Site::new();
+    site.file("app.css.br", &stored);
+    let app = StaticRouter::new(site.path())
+        .precompressed_br()
+        .auto_decompress(true)
+        .build();
+
+    let response = get_with(&app, "/app.css", &[("accept-encoding", "gzip")]).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "content-type"), Some("text/css"));
+    assert_eq!(header(&response, "content-encoding"), None);
+    assert_eq!(common::text(response).await, TEXT);
+
+    let response = get_with(&app, "/app.css", &[("accept-encoding", "br")]).await;
+    assert_eq!(header(&response, "content-encoding"), Some("br"));
+    assert_eq!(common::bytes(response).await, stored);
+}
+
+#[tokio::test]
+async fn precompressed_variants_follow_accept_encoding() {
+    let site = Site::with(&[
+        ("app.js", "plain"),
+        ("app.js.br", "brotli variant"),
+        ("app.js.gz", "gzip variant"),
+    ]);
+    let app = static_router_precompressed(site.path(), Encodings::new().br().gzip());
+
+    for (accept, encoding, body) in [
+        ("br, gzip", Some("br"), "brotli variant"),
+        ("gzip", Some("gzip"), "gzip variant"),
+        ("deflate", None, "plain"),
+    ] {
+        let response = get_with(&app, "/app.js", &[("accept-encoding", accept)]).await;
+        assert_eq!(response.status(), 200, "{accept}");
+        assert_eq!(header(&response, "content-encoding"), encoding, "{accept}");
+        assert_eq!(
+            header(&response, "content-type"),
+            Some("text/javascript"),
+            "{accept}"
+        );
+        assert_eq!(text(response).await, body, "{accept}");
+    }
+
+    let app = static_router_precompressed(site.path(), Encodings::new().gzip());
+    let response = get_with(&app, "/app.js", &[("accept-encoding", "br")]).await;
+    assert_eq!(header(&response, "content-encoding"), None);
+    assert_eq!(text(response).await, "plain");
+}
+
+#[tokio::test]
+async fn precompressed_zstd_is_served_to_zstd_clients() {
+    let site = Site::with(&[("app.wasm", "plain"), ("app.wasm.zst", "zstd variant")]);
+    let app = StaticRouter::new(site.path()).precompressed_zstd().build();
+
+    let response = get_with(&app, "/app.wasm", &[("accept-encoding", "gzip, zstd")]).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "content-encoding"), Some("zstd"));
+    assert_eq!(header(&response, "content-type"), Some("application/wasm"));
+    assert_eq!(text(response).await, "zstd variant");
+
+    let response = get_with(&app, "/app.wasm", &[("accept-encoding", "gzip, br")]).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "content-encoding"), None);
+    assert_eq!(header(&response, "content-type"), Some("application/wasm"));
+    assert_eq!(text(response).await, "plain");
+}
@@ -0,0 +1,191 @@
+//! Entity tags, `Last-Modified` and the conditional requests evaluated against them.
+
+mod common;
+
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, Response},
+};
+use axum_static::StaticRouter;
+use common::{Site, get, get_with, header, send, text};
+use std::time::{Duration, SystemTime};
+
+#[tokio::test]
+async fn directory_validates_against_its_index_file() {
+    let site = Site::with(&[("docs/index.html", "docs")]);
+    // Saturday, 11 January 2020, midnight UTC.
+    let index_modified = SystemTime::UNIX_EPOCH + Duration::from_hours(438_528);
+    site.modified("docs/index.html", index_modified);
+    let app = StaticRouter::new(site.path()).build();
+
+    // The directory itself was modified just now, when the index was written.
+    let since = [("if-modified-since", "Thu, 16 Jan 2020 00:00:00 GMT")];
+    let response = get_with(&app, "/docs/", &since).await;
+    assert_eq!(response.status(), 304);
+
+    let since = [("if-modified-since", "Wed, 01 Jan 2020 00:00:00 GMT")];
+    let response = get_with(&app, "/docs/", &since).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        header(&response, "last-modified"),
+        Some("Sat, 11 Jan 2020 00:00:00 GMT")
+    );
+}
+
+#[tokio::test]
+async fn if_match_fails_unless_a_tag_matches() {
+    let site = Site::with(&[("app.js", "js")]);
+    let app = StaticRouter::new(site.path()).honor_if_match(true).build();
+
+    let response = get(&app, "/app.js").await;
+    let etag = header(&response, "etag").expect("ETag").to_owned();
+
+    let response = get_with(&app, "/app.js", &[("if-match", r#""other""#)]).await;
+    assert_eq!(response.status(), 412);
+
+    let listed = format!(r#""other", {etag}"#);
+    let response = get_with(&app, "/app.js", &[("if-match", &listed)]).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(text(response).await, "js");
+
+    let response = get_with(&app, "/app.js", &[("if-match", "*")]).await;
+    assert_eq!(response.status(), 200);
+
+    let app = StaticRouter::new(site.path()).build();
+    let response = get_with(&app, "/app.js", &[("if-match", r#""other""#)]).await;
+    assert_eq!(response.status(), 200);
+}
+
+#[tokio::test]
+async fn etag_prefix_is_emitted_and_compared() {
+    let site = Site::with(&[("app.js", "js")]);
+    let app = StaticRouter::new(site.path())
+        .etag(true)
+        .etag_prefix("v2-")
+        .build();
+
+    let response = get(&app, "/app.js").await;
+    let etag = header(&response, "etag").expect("ETag").to_owned();
+    assert!(etag.starts_with(r#""v2-"#), "{etag}");
+
+    let response = get_with(&app, "/app.js", &[("if-none-match", &etag)]).await;
+    assert_eq!(response.status(), 304);
+
+    let unprefixed = etag.replacen("v2-", "", 1);
+    let response = get_with(&app, "/app.js", &[("if-none-match", &unprefixed)]).await;
+    assert_eq!(response.status(), 200);
+}
+
+#[tokio::test]
+async fn content_only_etags_ignore_modification_times() {
+    let site = Site::with(&[("a.css", "a {}"), ("b.css", "a {}"), ("c.css", "c {}")]);
+    site.modified("a.css", SystemTime::now() - Duration::from_hours(48));
+    let etag = |app: &Router, path: &'static str| {
+        let app = app.clone();
+        async move {
+            let response = get(&app, path).await;
+            header(&response, "etag").expect("ETag").to_owned()
+        }
+    };
+
+    let app = StaticRouter::new(site.path())
+        .etag_content_only(true)
+        .build();
+    let tag = etag(&app, "/a.css").await;
+    assert!(tag.starts_with("W/\""), "{tag}");
+    assert_eq!(etag(&app, "/b.css").await, tag);
+    assert_ne!(etag(&app, "/c.css").await, tag);
+
+    // A redeploy rewrites the file with a new modification time.
+    site.file("a.css", "a {}");
+    let response = get_with(&app, "/a.css", &[("if-none-match", &tag)]).await;
+    assert_eq!(response.status(), 304);
+    site.file("a.css", "a { }");
+    assert_ne!(etag(&app, "/a.css").await, tag);
+
+    let app = StaticRouter::new(site.path()).etag(true).build();
+    site.modified("b.css", SystemTime::now() - Duration::from_hours(48));
+    site.file("c.css", "a {}");
+    assert_ne!(etag(&app, "/b.css").await, etag(&app, "/c.css").await);
+}
+
+#[tokio::test]
+async fn etag_manifest_tags_listed_files() {
+    let site = Site::with(&[
+        ("assets/app.js", "js"),
+        ("logo.png", "png"),
+        ("style.css", "a {}"),
+    ]);
+    let app = StaticRouter::new(site.path())
+        .etag_prefix("v2-")
+        .etag_manifest([("assets/app.js", "5d41402a"), ("/logo.png", r#"W/"abc""#)])
+        .build();
+
+    let response = get(&app, "/assets/app.js").await;
+    assert_eq!(header(&response, "etag"), Some(r#""5d41402a""#));
+    let response = get_with(
+        &app,
+        "/assets/app.js",
+        &[("if-none-match", r#""5d41402a""#)],
+    )
+    .await;
+    assert_eq!(response.status(), 304);
+    let response = get(&app, "/logo.png").await;
+    assert_eq!(header(&response, "etag"), Some(r#"W/"abc""#));
+
+    let computed = StaticRouter::new(site.path())
+        .etag(true)
+        .etag_prefix("v2-")
+        .build();
+    let response = get(&computed, "/style.css").await;
+    let expected = header(&response, "etag").expect("ETag").to_owned();
+    let response = get(&app, "/style.css").await;
+    assert_eq!(header(&response, "etag"), Some(expected.as_str()));
+}
+
+#[tokio::test]
+async fn head_validators_match_get() {
+    let site = Site::with(&[
+        ("app.js", &"let x = 1;\n".repeat(10)),
+        ("index.html", "home"),
+    ]);
+    assert_head_matches_get(&StaticRouter::new(site.path()).build()).await;
+    assert_head_matches_get(&StaticRouter::new(site.path()).etag(true).build()).await;
+    assert_head_matches_get(
+        &StaticRouter::new(site.path())
+            .etag(true)
+            .spa_fallback(true)
+            .build(),
+    )
+    .await;
+    #[cfg(feature = "compression")]
+    {
+        let app = StaticRouter::new(site.path()).etag(true).compress(true);
+        assert_head_matches_get(&app.build()).await;
+        let app = StaticRouter::new(site.path())
+            .etag(true)
+            .compress_brotli(true);
+        assert_head_matches_get(&app.build()).await;
+    }
+}
+
+/// Asserts that `HEAD` requests to `app` get the validators and coding of a `GET`.
+async fn assert_head_matches_get(app: &Router) {
+    for uri in ["/app.js", "/", "/route"] {
+        let get = get_with(app, uri, &[("accept-encoding", "gzip, br")]).await;
+        let head = Request::head(uri)
+            .header("accept-encoding", "gzip, br")
+            .body(Body::empty())
+            .expect("request");
+        let head = send(app, head).await;
+        assert_eq!(get.status(), head.status(), "{uri}");
+        assert_eq!(representation(&get), representation(&head), "{uri}");
+    }
+}
+
+/// Returns the headers of `response` that describe the served representation.
+fn representation(response: &Response<Body>) -> [Option<String>; 4] {
+    ["etag", "last-modified", "content-encoding", "vary"]
+        .map(|name| header(response, name).map(str::to_owned))
+}
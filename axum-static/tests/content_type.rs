@@ -0,0 +1,261 @@
+//! Content types inferred for served files.
+
+mod common;
+
+use axum::{Router, middleware::from_fn};
+use axum_static::resolver::{Builtin, ExtensionMap, Fixed, Lookup, PathMap, Sniff};
+use axum_static::{ExtensionStrategy, StaticRouter, content_type_middleware, static_router_with};
+use common::{Site, get, header, text};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[tokio::test]
+async fn index_file_is_labelled_by_its_own_extension() {
+    let site = Site::with(&[("api/index.json", r#"{"ok":true}"#)]);
+    let app = StaticRouter::new(site.path())
+        .index_file("index.json")
+        .build();
+
+    let response = get(&app, "/api/").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "content-type"), Some("application/json"));
+    assert_eq!(text(response).await, r#"{"ok":true}"#);
+}
+
+#[tokio::test]
+async fn modern_image_formats_are_inferred() {
+    let site = Site::with(&[("a.avif", "avif"), ("b.heic", "heic"), ("c.jxl", "jxl")]);
+    let app = StaticRouter::new(site.path()).build();
+
+    for (path, mime) in [
+        ("/a.avif", "image/avif"),
+        ("/b.heic", "image/heic"),
+        ("/c.jxl", "image/jxl"),
+    ] {
+        let response = get(&app, path).await;
+        assert_eq!(header(&response, "content-type"), Some(mime), "{path}");
+    }
+}
+
+#[tokio::test]
+async fn uppercase_extensions_match_lowercase_ones() {
+    let site = Site::with(&[("FILE.PDF", "%PDF"), ("Photo.JPG", "jpeg")]);
+    let app = StaticRouter::new(site.path()).build();
+
+    let response = get(&app, "/FILE.PDF").await;
+    assert_eq!(header(&response, "content-type"), Some("application/pdf"));
+
+    let response = get(&app, "/Photo.JPG").await;
+    assert_eq!(header(&response, "content-type"), Some("image/jpeg"));
+}
+
+#[tokio::test]
+async fn sidecar_meta_types_files_without_a_known_extension() {
+    let site = Site::with(&[
+        ("data", "custom"),
+        ("data.meta", "application/x-custom\nignored\n"),
+        ("page.html", "<p>page</p>"),
+        ("page.html.meta", "application/x-custom"),
+    ]);
+    let app = StaticRouter::new(site.path()).sidecar_mime(true).build();
+
+    let response = get(&app, "/data").await;
+    assert_eq!(
+        header(&response, "content-type"),
+        Some("application/x-custom")
+    );
+    assert_eq!(text(response).await, "custom");
+
+    let response = get(&app, "/page.html").await;
+    assert_eq!(header(&response, "content-type"), Some("text/html"));
+
+    let app = StaticRouter::new(site.path()).build();
+    let response = get(&app, "/data").await;
+    assert_ne!(
+        header(&response, "content-type"),
+        Some("application/x-custom")
+    );
+}
+
+#[tokio::test]
+async fn extension_comes_from_the_last_path_segment() {
+    let app = Router::new()
+        .fallback(|| async { "body" })
+        .layer(from_fn(content_type_middleware));
+
+    for (path, mime) in [
+        ("/assets/v1.2/logo", "unknown"),
+        ("/download", "unknown"),
+        ("/v1.2/docs/", "unknown"),
+        ("/v1.2/app.js", "text/javascript"),
+        ("/archive.tar.gz", "application/gzip"),
+    ] {
+        let response = get(&app, path).await;
+        assert_eq!(header(&response, "content-type"), Some(mime), "{path}");
+    }
+}
+
+#[tokio::test]
+async fn compound_extension_strategies() {
+    let site = Site::with(&[
+        ("archive.tar.gz", "tgz"),
+        ("app.min.js", "js"),
+        ("plain.gz", "gz"),
+    ]);
+
+    for (strategy, tar_gz) in [
+        (ExtensionStrategy::LastSegment, "application/gzip"),
+        (ExtensionStrategy::MostSpecific, "application/x-gtar"),
+    ] {
+        let app = StaticRouter::new(site.path())
+            .compound_extension_strategy(strategy)
+            .build();
+
+        let response = get(&app, "/archive.tar.gz").await;
+        assert_eq!(
+            header(&response, "content-type"),
+            Some(tar_gz),
+            "{strategy:?}"
+        );
+        let response = get(&app, "/app.min.js").await;
+        assert_eq!(
+            header(&response, "content-type"),
+            Some("text/javascript"),
+            "{strategy:?}"
+        );
+        let response = get(&app, "/plain.gz").await;
+        assert_eq!(
+            header(&response, "content-type"),
+            Some("application/gzip"),
+            "{strategy:?}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn static_router_with_overrides_extension_types() {
+    let site = Site::with(&[
+        ("scene.GLB", "glTF"),
+        ("data.myapp", "custom"),
+        ("page.html", "<p>page</p>"),
+        ("app.js", "js"),
+    ]);
+    let app = static_router_with(
+        site.path(),
+        [
+            ("glb", "model/gltf-binary"),
+            ("MyApp", "application/x-myapp"),
+            ("html", "application/xhtml+xml"),
+        ],
+    );
+
+    for (path, mime) in [
+        ("/scene.GLB", "model/gltf-binary"),
+        ("/data.myapp", "application/x-myapp"),
+        ("/page.html", "application/xhtml+xml"),
+        ("/app.js", "text/javascript"),
+    ] {
+        let response = get(&app, path).await;
+        assert_eq!(response.status(), 200, "{path}");
+        assert_eq!(header(&response, "content-type"), Some(mime), "{path}");
+    }
+}
+
+#[tokio::test]
+async fn query_and_fragment_remnants_are_stripped_from_extensions() {
+    let app = Router::new()
+        .fallback(|| async { "body" })
+        .layer(from_fn(content_type_middleware));
+
+    for (uri, mime) in [
+        ("/app.js?v=1", "text/javascript"),
+        ("/app.js%3Fv=1", "text/javascript"),
+        ("/style.css%23x", "text/css"),
+        ("/style.css;", "text/css"),
+        ("/style.css", "text/css"),
+    ] {
+        let response = get(&app, uri).await;
+        assert_eq!(header(&response, "content-type"), Some(mime), "{uri}");
+    }
+}
+
+#[tokio::test]
+async fn mime_types_file_supplies_and_overrides_types() {
+    let site = Site::with(&[
+        ("notes.md", "# notes"),
+        ("notes.markdown", "# notes"),
+        ("data.myapp", "custom"),
+        ("app.js", "js"),
+    ]);
+    let config = Site::with(&[(
+        "mime.types",
+        "# custom formats\n\
+         application/x-myapp\tmyapp   # proprietary\n\
+         \n\
+         text/markdown md markdown\n\
+         application/x-old js\n\
+         text/x-js js\n",
+    )]);
+    let app = StaticRouter::new(site.path())
+        .mime_types_file(config.path().join("mime.types"))
+        .expect("valid mime.types")
+        .build();
+
+    for (path, mime) in [
+        ("/notes.md", "text/markdown"),
+        ("/notes.markdown", "text/markdown"),
+        ("/data.myapp", "application/x-myapp"),
+        ("/app.js", "text/x-js"),
+    ] {
+        let response = get(&app, path).await;
+        assert_eq!(header(&response, "content-type"), Some(mime), "{path}");
+    }
+
+    let config = Site::with(&[("mime.types", "not a type\tbad\n")]);
+    let err = StaticRouter::new(site.path())
+        .mime_types_file(config.path().join("mime.types"))
+        .expect_err("invalid media type");
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[tokio::test]
+async fn resolver_chain_stops_at_the_first_answer() {
+    let site = Site::with(&[
+        ("feed", "<feed/>"),
+        ("scene.glb", "glTF"),
+        ("app.js", "js"),
+        ("blob", "??"),
+    ]);
+    site.file("image", b"\x89PNG\r\n\x1a\n....");
+    let deferred = Arc::new(AtomicUsize::new(0));
+    let calls = Arc::clone(&deferred);
+    let app = StaticRouter::new(site.path())
+        .content_type_resolvers(vec![
+            Box::new(PathMap::new([("/feed", "application/atom+xml")])),
+            Box::new(ExtensionMap::new([
+                ("glb", "model/gltf-binary"),
+                ("js", "text/x-js"),
+            ])),
+            Box::new(move |_: &Lookup<'_>| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                None
+            }),
+            Box::new(Builtin),
+            Box::new(Sniff),
+            Box::new(Fixed::new("application/x-unknown")),
+        ])
+        .build();
+
+    for (path, mime, reached) in [
+        ("/feed", "application/atom+xml", 0),
+        ("/scene.glb", "model/gltf-binary", 0),
+        ("/app.js", "text/x-js", 0),
+        ("/image", "image/png", 1),
+        ("/blob", "application/x-unknown", 2),
+    ] {
+        let response = get(&app, path).await;
+        assert_eq!(response.status(), 200, "{path}");
+        assert_eq!(header(&response, "content-type"), Some(mime), "{path}");
+        assert_eq!(deferred.load(Ordering::SeqCst), reached, "{path}");
+    }
+}
@@ -0,0 +1,84 @@
+//! Cross-origin access to served files and CORS preflight responses.
+
+mod common;
+
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, Response},
+};
+use axum_static::StaticRouter;
+use common::{Site, get, header, send};
+use std::time::Duration;
+
+/// Sends a CORS preflight for a `GET` of `uri` that will send `headers`.
+async fn preflight(app: &Router, uri: &str, headers: &str) -> Response<Body> {
+    let request = Request::options(uri)
+        .header("origin", "https://app.example.com")
+        .header("access-control-request-method", "GET")
+        .header("access-control-request-headers", headers)
+        .body(Body::empty())
+        .expect("request");
+    send(app, request).await
+}
+
+#[tokio::test]
+async fn preflight_lists_configured_methods_and_headers() {
+    let site = Site::with(&[("video.mp4", "0123456789")]);
+    let app = StaticRouter::new(site.path())
+        .cors(true)
+        .cors_allow_methods(["GET", "HEAD"])
+        .cors_allow_headers(["Range", "X-Requested-With"])
+        .build();
+
+    let response = preflight(&app, "/video.mp4", "range").await;
+    assert_eq!(response.status(), 204);
+    assert_eq!(header(&response, "access-control-allow-origin"), Some("*"));
+    assert_eq!(
+        header(&response, "access-control-allow-methods"),
+        Some("GET, HEAD")
+    );
+    assert_eq!(
+        header(&response, "access-control-allow-headers"),
+        Some("range, x-requested-with")
+    );
+
+    let app = StaticRouter::new(site.path()).cors(true).build();
+    let response = preflight(&app, "/video.mp4", "range").await;
+    assert_eq!(
+        header(&response, "access-control-allow-methods"),
+        Some("GET, HEAD, OPTIONS")
+    );
+    assert_eq!(header(&response, "access-control-allow-headers"), None);
+
+    let response = get(&app, "/video.mp4").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "access-control-allow-origin"), Some("*"));
+}
+
+#[tokio::test]
+async fn cors_max_age_is_sent_on_preflights() {
+    let site = Site::with(&[("app.js", "js")]);
+    let app = StaticRouter::new(site.path())
+        .cors(true)
+        .cors_max_age(Duration::from_mins(10))
+        .build();
+
+    let response = preflight(&app, "/app.js", "range").await;
+    assert_eq!(response.status(), 204);
+    assert_eq!(header(&response, "access-control-max-age"), Some("600"));
+
+    let response = get(&app, "/app.js").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "access-control-max-age"), None);
+
+    let app = StaticRouter::new(site.path()).cors(true).build();
+    let response = preflight(&app, "/app.js", "range").await;
+    assert_eq!(header(&response, "access-control-max-age"), None);
+
+    let app = StaticRouter::new(site.path())
+        .cors_max_age(Duration::from_mins(10))
+        .build();
+    let response = preflight(&app, "/app.js", "range").await;
+    assert_eq!(header(&response, "access-control-max-age"), None);
+}
@@ -0,0 +1,140 @@
+//! Responses generated by the router instead of read from the root.
+
+mod common;
+
+use axum_static::StaticRouter;
+use common::{Site, get, header, text};
+
+#[tokio::test]
+async fn default_robots_txt_yields_to_a_real_file() {
+    let site = Site::new();
+    let app = StaticRouter::new(site.path())
+        .default_robots_txt("User-agent: *\nDisallow:\n")
+        .build();
+
+    let response = get(&app, "/robots.txt").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "content-type"), Some("text/plain"));
+    assert_eq!(text(response).await, "User-agent: *\nDisallow:\n");
+
+    site.file("robots.txt", "User-agent: *\nDisallow: /private/\n");
+    let response = get(&app, "/robots.txt").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(text(response).await, "User-agent: *\nDisallow: /private/\n");
+}
+
+#[tokio::test]
+async fn status_endpoint_reports_version_root_and_every_feature() {
+    let site = Site::with(&[("index.html", "home")]);
+    let app = StaticRouter::new(site.path())
+        .status_endpoint("/__status")
+        .maintenance_mode(true)
+        .build();
+
+    let response = get(&app, "/__status").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "content-type"), Some("application/json"));
+    let json = text(response).await;
+
+    let version = format!(r#"{{"version":"{}","#, env!("CARGO_PKG_VERSION"));
+    assert!(json.starts_with(&version), "{json}");
+    let root = site.path().to_string_lossy().replace('\\', "\\\\");
+    assert!(json.contains(&format!(r#""root":"{root}""#)), "{json}");
+    let features = [
+        ("cdn_fallback", cfg!(feature = "cdn_fallback")),
+        ("compression", cfg!(feature = "compression")),
+        ("handle_error", cfg!(feature = "handle_error")),
+        ("mime_guess", cfg!(feature = "mime_guess")),
+        ("status_code", cfg!(feature = "status_code")),
+        ("tracing", cfg!(feature = "tracing")),
+        ("unicode_normalize", cfg!(feature = "unicode_normalize")),
+    ]
+    .map(|(name, enabled)| format!(r#""{name}":{enabled}"#))
+    .join(",");
+    assert!(
+        json.ends_with(&format!(r#""features":{{{features}}}}}"#)),
+        "{json}"
+    );
+
+    let response = get(&app, "/index.html").await;
+    assert_eq!(response.status(), 503);
+}
+
+#[tokio::test]
+async fn generated_responses_are_not_cacheable_by_default() {
+    let site = Site::with(&[("guide.html", "guide")]);
+    let app = StaticRouter::new(site.path())
+        .status_endpoint("/__status")
+        .suggest_on_404(true)
+        .build();
+
+    let response = get(&app, "/__status").await;
+    assert_eq!(
+        header(&response, "cache-control"),
+        Some("private, no-store")
+    );
+
+    let response = get(&app, "/gide.html").await;
+    assert_eq!(response.status(), 404);
+    assert_eq!(
+        header(&response, "cache-control"),
+        Some("private, no-store")
+    );
+
+    let response = get(&app, "/guide.html").await;
+    assert_eq!(header(&response, "cache-control"), None);
+
+    let app = StaticRouter::new(site.path())
+        .status_endpoint("/__status")
+        .generated_cache_control("public, max-age=60")
+        .build();
+    let response = get(&app, "/__status").await;
+    assert_eq!(
+        header(&response, "cache-control"),
+        Some("public, max-age=60")
+    );
+}
+
+#[tokio::test]
+async fn default_security_txt_yields_to_a_real_file() {
+    let contact = "Contact: mailto:security@example.com\n";
+    let site = Site::new();
+    let app = StaticRouter::new(site.path())
+        .default_security_txt(contact)
+        .build();
+
+    let response = get(&app, "/.well-known/security.txt").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "content-type"), Some("text/plain"));
+    assert_eq!(text(response).await, contact);
+
+    site.file(
+        ".well-known/security.txt",
+        "Contact: https://example.com/report\n",
+    );
+    let response = get(&app, "/.well-known/security.txt").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        text(response).await,
+        "Contact: https://example.com/report\n"
+    );
+}
+
+#[tokio::test]
+async fn acme_challenges_are_answered_from_memory() {
+    let site = Site::with(&[(".well-known/acme-challenge/on-disk", "from disk")]);
+    let app = StaticRouter::new(site.path())
+        .acme_challenge([("token123", "token123.thumbprint")])
+        .maintenance_mode(true)
+        .build();
+
+    let response = get(&app, "/.well-known/acme-challenge/token123").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "content-type"), Some("text/plain"));
+    assert_eq!(text(response).await, "token123.thumbprint");
+
+    for token in ["unknown", "on-disk"] {
+        let response = get(&app, &format!("/.well-known/acme-challenge/{token}")).await;
+        assert_eq!(response.status(), 404, "{token}");
+    }
+}
@@ -0,0 +1,188 @@
+//! Response headers configured on the builder.
+
+mod common;
+
+use axum_static::{DigestAlgorithm, PreloadLink, StaticRouter};
+use common::{Site, get, header};
+
+#[tokio::test]
+async fn clear_site_data_only_on_configured_path() {
+    let site = Site::with(&[("logout.html", "bye"), ("index.html", "home")]);
+    let app = StaticRouter::new(site.path())
+        .clear_site_data_for_path("/logout.html", r#""cache", "cookies""#)
+        .build();
+
+    let response = get(&app, "/logout.html").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        header(&response, "clear-site-data"),
+        Some(r#""cache", "cookies""#)
+    );
+
+    let response = get(&app, "/index.html").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "clear-site-data"), None);
+}
+
+#[tokio::test]
+async fn preload_links_mark_push_intent() {
+    let site = Site::with(&[("index.html", "<h1>home</h1>"), ("app.css", "body {}")]);
+    let pushed = PreloadLink {
+        push: true,
+        ..PreloadLink::new("/app.css", "style")
+    };
+    let app = StaticRouter::new(site.path())
+        .preload_links([pushed, PreloadLink::new("/app.js", "script")])
+        .build();
+
+    let response = get(&app, "/index.html").await;
+    assert_eq!(
+        header(&response, "link"),
+        Some("</app.css>; rel=preload; as=style, </app.js>; rel=preload; as=script; nopush")
+    );
+
+    let response = get(&app, "/app.css").await;
+    assert_eq!(header(&response, "link"), None);
+}
+
+#[tokio::test]
+async fn accept_ch_only_on_html() {
+    let site = Site::with(&[("index.html", "<h1>home</h1>"), ("photo.png", "png")]);
+    let app = StaticRouter::new(site.path())
+        .accept_ch(["DPR", "Width"])
+        .build();
+
+    let response = get(&app, "/index.html").await;
+    assert_eq!(header(&response, "accept-ch"), Some("DPR, Width"));
+
+    let response = get(&app, "/photo.png").await;
+    assert_eq!(header(&response, "content-type"), Some("image/png"));
+    assert_eq!(header(&response, "accept-ch"), None);
+}
+
+#[tokio::test]
+async fn server_timing_reports_phases_that_ran() {
+    let site = Site::with(&[("app.js", "js"), ("index.html", "shell")]);
+    let app = StaticRouter::new(site.path()).server_timing(true).build();
+
+    let response = get(&app, "/app.js").await;
+    let timing = header(&response, "server-timing").expect("Server-Timing");
+    let names = metric_names(timing);
+    assert_eq!(names, ["stream", "infer"]);
+    assert!(timing.contains("stream;dur="));
+
+    let app = StaticRouter::new(site.path())
+        .server_timing(true)
+        .spa_fallback(true)
+        .build();
+    let response = get(&app, "/route").await;
+    let timing = header(&response, "server-timing").expect("Server-Timing");
+    assert_eq!(metric_names(timing), ["stat", "stream", "infer"]);
+
+    let app = StaticRouter::new(site.path()).build();
+    let response = get(&app, "/app.js").await;
+    assert_eq!(header(&response, "server-timing"), None);
+}
+
+/// Returns the metric names of a `Server-Timing` value.
+fn metric_names(timing: &str) -> Vec<&str> {
+    timing
+        .split(", ")
+        .map(|metric| metric.split(';').next().unwrap_or_default())
+        .collect()
+}
+
+#[tokio::test]
+async fn cross_origin_isolation_only_on_html() {
+    let site = Site::with(&[("index.html", "<h1>home</h1>"), ("photo.png", "png")]);
+    let app = StaticRouter::new(site.path())
+        .cross_origin_isolation(true)
+        .build();
+
+    let response = get(&app, "/index.html").await;
+    assert_eq!(
+        header(&response, "cross-origin-opener-policy"),
+        Some("same-origin")
+    );
+    assert_eq!(
+        header(&response, "cross-origin-embedder-policy"),
+        Some("require-corp")
+    );
+
+    let response = get(&app, "/photo.png").await;
+    assert_eq!(header(&response, "cross-origin-opener-policy"), None);
+    assert_eq!(header(&response, "cross-origin-embedder-policy"), None);
+}
+
+#[tokio::test]
+async fn strip_headers_removes_listed_names_in_any_case() {
+    let site = Site::with(&[("app.js", "js")]);
+    let app = StaticRouter::new(site.path())
+        .cache_control_for_extension("js", "no-cache")
+        .strip_headers(["LAST-MODIFIED", "Accept-Ranges", "cache-control"])
+        .build();
+
+    let response = get(&app, "/app.js").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "last-modified"), None);
+    assert_eq!(header(&response, "accept-ranges"), None);
+    assert_eq!(header(&response, "cache-control"), None);
+    assert_eq!(header(&response, "content-type"), Some("text/javascript"));
+    assert_eq!(header(&response, "content-length"), Some("2"));
+}
+
+#[tokio::test]
+async fn digest_header_matches_the_file_hash() {
+    let site = Site::with(&[("hello.txt", "hello")]);
+    site.file("large.bin", vec![0; 16 * 1024 * 1024 + 1]);
+    let app = StaticRouter::new(site.path())
+        .digest_header(DigestAlgorithm::Sha256)
+        .build();
+
+    let response = get(&app, "/hello.txt").await;
+    assert_eq!(
+        header(&response, "digest"),
+        Some("sha-256=LPJNul+wow4m6DsqxbninhsWHlwfp0JecwQzYpOLmCQ=")
+    );
+
+    let response = get(&app, "/large.bin").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "digest"), None);
+}
+
+#[tokio::test]
+async fn canonical_link_names_the_directory_of_index_pages() {
+    let site = Site::with(&[
+        ("index.html", "home"),
+        ("docs/index.html", "docs"),
+        ("docs/guide.html", "guide"),
+        ("docs/app.js", "js"),
+    ]);
+    let app = StaticRouter::new(site.path())
+        .canonical_link("https://example.com/")
+        .build();
+
+    for (uri, canonical) in [
+        ("/", "https://example.com/"),
+        ("/index.html", "https://example.com/"),
+        ("/docs/", "https://example.com/docs/"),
+        ("/docs/index.html", "https://example.com/docs/"),
+        (
+            "/docs/guide.html?ref=nav",
+            "https://example.com/docs/guide.html",
+        ),
+    ] {
+        let response = get(&app, uri).await;
+        assert_eq!(
+            header(&response, "link"),
+            Some(format!("<{canonical}>; rel=\"canonical\"").as_str()),
+            "{uri}"
+        );
+    }
+
+    let response = get(&app, "/docs/app.js").await;
+    assert_eq!(header(&response, "link"), None);
+    let response = get(&app, "/missing.html").await;
+    assert_eq!(response.status(), 404);
+    assert_eq!(header(&response, "link"), None);
+}
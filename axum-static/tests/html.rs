@@ -0,0 +1,278 @@
+//! HTML and JSON bodies rewritten before they are sent.
+
+mod common;
+
+use axum::{body::Body, http::Request};
+use axum_static::StaticRouter;
+use common::{Site, bytes, get, get_with, header, send, text};
+use std::time::{Duration, SystemTime};
+
+const NONCED_PAGE: &str = r#"<script nonce="__CSP_NONCE__">run()</script>"#;
+
+/// Returns the nonce of a `script-src 'nonce-…'` policy.
+fn policy_nonce(policy: &str) -> &str {
+    policy
+        .strip_prefix("script-src 'nonce-")
+        .and_then(|rest| rest.strip_suffix('\''))
+        .expect("nonce policy")
+}
+
+#[tokio::test]
+async fn csp_nonce_matches_header_and_body() {
+    let site = Site::with(&[("index.html", NONCED_PAGE)]);
+    let app = StaticRouter::new(site.path()).csp_nonce(true).build();
+
+    let response = get(&app, "/index.html").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "cache-control"), Some("no-store"));
+    let policy = header(&response, "content-security-policy").expect("CSP");
+    let nonce = policy_nonce(policy).to_owned();
+    assert_eq!(nonce.len(), 24);
+    assert_eq!(
+        text(response).await,
+        format!(r#"<script nonce="{nonce}">run()</script>"#)
+    );
+
+    let response = get(&app, "/index.html").await;
+    let policy = header(&response, "content-security-policy").expect("CSP");
+    assert_ne!(policy_nonce(policy), nonce);
+}
+
+#[tokio::test]
+async fn csp_nonce_keeps_bytes_that_are_not_utf8() {
+    let site = Site::new();
+    site.file(
+        "index.html",
+        b"<p>caf\xe9</p><script nonce=\"__CSP_NONCE__\"></script>",
+    );
+    let app = StaticRouter::new(site.path()).csp_nonce(true).build();
+
+    let response = get(&app, "/index.html").await;
+    let policy = header(&response, "content-security-policy").expect("CSP");
+    let nonce = policy_nonce(policy).to_owned();
+    let expected = [
+        &b"<p>caf\xe9</p><script nonce=\""[..],
+        nonce.as_bytes(),
+        b"\"></script>",
+    ]
+    .concat();
+    assert_eq!(bytes(response).await, expected);
+}
+
+#[tokio::test]
+async fn csp_nonce_pages_have_no_validators_or_ranges() {
+    let site = Site::with(&[("index.html", NONCED_PAGE)]);
+    site.file("index.html.gz", "stale precompressed page");
+    let app = StaticRouter::new(site.path())
+        .csp_nonce(true)
+        .etag(true)
+        .precompressed_gzip()
+        .build();
+
+    let response = get(&app, "/index.html").await;
+    assert_eq!(header(&response, "etag"), None);
+    assert_eq!(header(&response, "last-modified"), None);
+    assert_eq!(header(&response, "accept-ranges"), None);
+
+    let response = get_with(&app, "/index.html", &[("range", "bytes=0-10")]).await;
+    assert_eq!(response.status(), 200);
+    let body = text(response).await;
+    assert!(body.starts_with("<script nonce=\""), "{body}");
+    assert!(!body.contains("__CSP_NONCE__"), "{body}");
+
+    let response = get_with(&app, "/index.html", &[("accept-encoding", "gzip")]).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "content-encoding"), None);
+    assert!(!text(response).await.contains("__CSP_NONCE__"));
+}
+
+#[tokio::test]
+async fn json_is_wrapped_in_the_callback() {
+    let site = Site::with(&[("data.json", r#"{"ok":true}"#)]);
+    let app = StaticRouter::new(site.path()).allow_jsonp(true).build();
+
+    let response = get(&app, "/data.json?callback=app.handle").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        header(&response, "content-type"),
+        Some("application/javascript")
+    );
+    assert_eq!(header(&response, "etag"), None);
+    assert_eq!(text(response).await, r#"/**/app.handle({"ok":true});"#);
+
+    let response = get(&app, "/data.json").await;
+    assert_eq!(header(&response, "content-type"), Some("application/json"));
+    assert_eq!(text(response).await, r#"{"ok":true}"#);
+}
+
+#[tokio::test]
+async fn invalid_callbacks_are_rejected() {
+    let site = Site::with(&[("data.json", "{}")]);
+    let app = StaticRouter::new(site.path()).allow_jsonp(true).build();
+
+    for callback in ["alert(1)", "1up", "a..b", "%3Cscript%3E"] {
+        let response = get(&app, &format!("/data.json?callback={callback}")).await;
+        assert_eq!(response.status(), 400, "{callback}");
+    }
+}
+
+#[tokio::test]
+async fn head_requests_get_jsonp_headers_without_a_body() {
+    let site = Site::with(&[("data.json", "{}")]);
+    let app = StaticRouter::new(site.path()).allow_jsonp(true).build();
+
+    let request = Request::head("/data.json?callback=cb")
+        .body(Body::empty())
+        .expect("request");
+    let response = send(&app, request).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        header(&response, "content-type"),
+        Some("application/javascript")
+    );
+    assert!(bytes(response).await.is_empty());
+}
+
+#[tokio::test]
+async fn oversized_json_is_served_unwrapped() {
+    let json = format!("[{}0]", "0,".repeat(9 * 1024 * 1024));
+    let site = Site::new();
+    site.file("big.json", &json);
+    let app = StaticRouter::new(site.path()).allow_jsonp(true).build();
+
+    let response = get(&app, "/big.json?callback=cb").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "content-type"), Some("application/json"));
+    assert_eq!(bytes(response).await.len(), json.len());
+}
+
+#[tokio::test]
+async fn oversized_pages_are_served_without_a_nonce() {
+    let page = format!("{NONCED_PAGE}{}", " ".repeat(17 * 1024 * 1024));
+    let site = Site::new();
+    site.file("index.html", &page);
+    let app = StaticRouter::new(site.path()).csp_nonce(true).build();
+
+    let response = get(&app, "/index.html").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "content-security-policy"), None);
+    assert_eq!(bytes(response).await.len(), page.len());
+}
+
+#[tokio::test]
+async fn cache_bust_versions_only_local_references() {
+    let page = concat!(
+        r#"<script src="app.js"></script>"#,
+        r#"<script src="https://cdn.example.com/lib.js"></script>"#,
+        r#"<link href="//fonts.example.com/a.css"><a href="missing.html">x</a>"#,
+    );
+    let site = Site::with(&[("docs/index.html", page), ("docs/app.js", "console.log(1)")]);
+    let app = StaticRouter::new(site.path()).cache_bust_html(true).build();
+
+    let response = get(&app, "/docs/").await;
+    assert_eq!(header(&response, "etag"), None);
+    assert_eq!(
+        text(response).await,
+        concat!(
+            r#"<script src="app.js?v=0a286891c11c056e"></script>"#,
+            r#"<script src="https://cdn.example.com/lib.js"></script>"#,
+            r#"<link href="//fonts.example.com/a.css"><a href="missing.html">x</a>"#,
+        )
+    );
+}
+
+#[tokio::test]
+async fn cache_bust_keeps_bytes_that_are_not_utf8() {
+    let site = Site::new();
+    site.file(
+        "index.html",
+        b"<p>caf\xe9</p><script src=\"app.js\"></script>",
+    )
+    .file("app.js", "console.log(1)");
+    let app = StaticRouter::new(site.path()).cache_bust_html(true).build();
+
+    let response = get(&app, "/index.html").await;
+    assert_eq!(
+        bytes(response).await,
+        &b"<p>caf\xe9</p><script src=\"app.js?v=0a286891c11c056e\"></script>"[..]
+    );
+}
+
+#[tokio::test]
+async fn cache_bust_rehashes_only_changed_files() {
+    let written = SystemTime::UNIX_EPOCH + Duration::from_hours(1);
+    let site = Site::with(&[("index.html", r#"<script src="app.js"></script>"#)]);
+    site.file("app.js", "console.log(1)")
+        .modified("app.js", written);
+    let app = StaticRouter::new(site.path()).cache_bust_html(true).build();
+    let first = text(get(&app, "/index.html").await).await;
+
+    site.file("app.js", "console.log(2)")
+        .modified("app.js", written);
+    assert_eq!(text(get(&app, "/index.html").await).await, first);
+
+    site.modified("app.js", written + Duration::from_secs(1));
+    let changed = text(get(&app, "/index.html").await).await;
+    assert_ne!(changed, first);
+    assert!(
+        changed.starts_with(r#"<script src="app.js?v="#),
+        "{changed}"
+    );
+}
+
+#[tokio::test]
+async fn server_side_includes_keep_bytes_that_are_not_utf8() {
+    let site = Site::new();
+    site.file("index.html", b"caf\xe9 <!--#include file=\"nav.html\"-->")
+        .file("nav.html", b"<nav>\x93links\x94</nav>");
+    let app = StaticRouter::new(site.path())
+        .server_side_includes(true)
+        .build();
+
+    let response = get(&app, "/index.html").await;
+    assert_eq!(header(&response, "content-length"), None);
+    assert_eq!(
+        bytes(response).await,
+        &b"caf\xe9 <nav>\x93links\x94</nav>"[..]
+    );
+}
+
+#[tokio::test]
+async fn server_side_includes_compose_fragments() {
+    let site = Site::with(&[
+        (
+            "public/index.html",
+            r#"<!--#include file="partials/head.html"--><main>home</main>"#,
+        ),
+        (
+            "public/partials/head.html",
+            r#"<header><!--#include virtual="/partials/nav.html"--></header>"#,
+        ),
+        ("public/partials/nav.html", "<nav>links</nav>"),
+        (
+            "public/escape.html",
+            r#"<!--#include file="../secret.txt"--><!--#include virtual="/%2e%2e/secret.txt"-->"#,
+        ),
+        ("public/loop.html", r#"a<!--#include file="loop-b.html"-->"#),
+        ("public/loop-b.html", r#"b<!--#include file="loop.html"-->"#),
+        ("secret.txt", "secret"),
+    ]);
+    let app = StaticRouter::new(site.path().join("public"))
+        .server_side_includes(true)
+        .build();
+
+    let response = get(&app, "/").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "etag"), None);
+    assert_eq!(
+        text(response).await,
+        "<header><nav>links</nav></header><main>home</main>"
+    );
+
+    let error = "[an error occurred while processing this directive]";
+    let response = get(&app, "/escape.html").await;
+    assert_eq!(text(response).await, error.repeat(2));
+
+    let response = get(&app, "/loop.html").await;
+    assert_eq!(text(response).await, format!("ab{error}"));
+}
@@ -0,0 +1,86 @@
+//! Negotiation of language and image format variants.
+
+mod common;
+
+use axum_static::StaticRouter;
+use common::{Site, get_with, header, text};
+
+#[tokio::test]
+async fn language_chain_falls_back_to_english_variant() {
+    let site = Site::with(&[("page.html", "base"), ("page.en.html", "english")]);
+    let app = StaticRouter::new(site.path())
+        .language_fallback_chain(["fr", "en"])
+        .build();
+
+    let response = get_with(
+        &app,
+        "/page.html",
+        &[("accept-language", "fr-CA, fr;q=0.9")],
+    )
+    .await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "content-type"), Some("text/html"));
+    assert_eq!(header(&response, "vary"), Some("accept-language"));
+    assert_eq!(text(response).await, "english");
+
+    site.file("page.fr.html", "français");
+    let response = get_with(&app, "/page.html", &[("accept-language", "fr")]).await;
+    assert_eq!(text(response).await, "français");
+
+    let app = StaticRouter::new(site.path())
+        .language_fallback_chain(["de"])
+        .build();
+    let response = get_with(&app, "/page.html", &[("accept-language", "ja")]).await;
+    assert_eq!(text(response).await, "base");
+}
+
+#[tokio::test]
+async fn image_negotiation_serves_the_best_accepted_sibling() {
+    let site = Site::with(&[
+        ("photo.jpg", "jpeg"),
+        ("photo.webp", "webp"),
+        ("photo.avif", "avif"),
+    ]);
+    let app = StaticRouter::new(site.path())
+        .image_negotiation(true)
+        .build();
+
+    let accept = [("accept", "image/avif,image/webp,*/*")];
+    let response = get_with(&app, "/photo.jpg", &accept).await;
+    assert_eq!(header(&response, "content-type"), Some("image/avif"));
+    assert!(header(&response, "vary").is_some_and(|vary| vary.contains("accept")));
+    assert_eq!(text(response).await, "avif");
+
+    let response = get_with(&app, "/photo.jpg", &[("accept", "image/webp,*/*")]).await;
+    assert_eq!(header(&response, "content-type"), Some("image/webp"));
+    assert_eq!(text(response).await, "webp");
+
+    let response = get_with(&app, "/photo.jpg", &[("accept", "*/*")]).await;
+    assert_eq!(header(&response, "content-type"), Some("image/jpeg"));
+    assert_eq!(text(response).await, "jpeg");
+}
+
+#[tokio::test]
+async fn plaintext_view_serves_html_source_when_preferred() {
+    let site = Site::with(&[("index.html", "<h1>home</h1>")]);
+    let app = StaticRouter::new(site.path())
+        .allow_plaintext_view(true)
+        .build();
+
+    let response = get_with(&app, "/index.html", &[("accept", "text/plain")]).await;
+    assert_eq!(header(&response, "content-type"), Some("text/plain"));
+    assert_eq!(header(&response, "vary"), Some("accept"));
+    assert_eq!(text(response).await, "<h1>home</h1>");
+
+    let browser = [("accept", "text/html,application/xhtml+xml,*/*;q=0.8")];
+    let response = get_with(&app, "/index.html", &browser).await;
+    assert_eq!(header(&response, "content-type"), Some("text/html"));
+
+    let response = get_with(
+        &app,
+        "/index.html",
+        &[("accept", "text/plain;q=0.5, text/html")],
+    )
+    .await;
+    assert_eq!(header(&response, "content-type"), Some("text/html"));
+}
@@ -0,0 +1,47 @@
+//! Unicode normalization of request paths.
+
+#![cfg(feature = "unicode_normalize")]
+
+mod common;
+
+use axum_static::{NormalizationForm, StaticRouter};
+use common::{Site, get, header, text};
+
+/// `café` with `e` followed by a combining acute accent, percent-encoded.
+const DECOMPOSED: &str = "cafe%CC%81";
+
+/// `café` with a precomposed `é`, percent-encoded.
+const COMPOSED: &str = "caf%C3%A9";
+
+#[tokio::test]
+async fn decomposed_requests_resolve_composed_names() {
+    let site = Site::with(&[("menu/caf\u{e9}.txt", "espresso")]);
+    let app = StaticRouter::new(site.path())
+        .unicode_normalize(NormalizationForm::Nfc)
+        .build();
+
+    let response = get(&app, &format!("/menu/{DECOMPOSED}.txt")).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "content-type"), Some("text/plain"));
+    assert_eq!(text(response).await, "espresso");
+    let response = get(&app, &format!("/menu/{COMPOSED}.txt")).await;
+    assert_eq!(response.status(), 200);
+
+    let app = StaticRouter::new(site.path()).build();
+    let response = get(&app, &format!("/menu/{DECOMPOSED}.txt")).await;
+    assert_eq!(response.status(), 404);
+}
+
+#[tokio::test]
+async fn composed_requests_resolve_decomposed_names() {
+    let site = Site::with(&[("cafe\u{301}/index.html", "menu")]);
+    let app = StaticRouter::new(site.path())
+        .unicode_normalize(NormalizationForm::Nfd)
+        .build();
+
+    let response = get(&app, &format!("/{COMPOSED}/")).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(text(response).await, "menu");
+    let response = get(&app, &format!("/{COMPOSED}")).await;
+    assert!(response.status().is_redirection());
+}
@@ -0,0 +1,85 @@
+//! Range requests and their validators.
+
+mod common;
+
+use axum::{Router, middleware::from_fn, routing::get as route};
+use axum_static::{StaticRouter, content_type_middleware};
+use common::{Site, get_with, header, text};
+
+const MULTIPART: &str = "multipart/byteranges; boundary=3d6b6a416f9b5";
+
+#[tokio::test]
+async fn multipart_byteranges_type_is_kept() {
+    let app = Router::new()
+        .route(
+            "/video.mp4",
+            route(|| async {
+                (
+                    axum::http::StatusCode::PARTIAL_CONTENT,
+                    [("content-type", MULTIPART)],
+                    "parts",
+                )
+            }),
+        )
+        .layer(from_fn(content_type_middleware));
+
+    let response = get_with(&app, "/video.mp4", &[("range", "bytes=0-1,3-4")]).await;
+    assert_eq!(response.status(), 206);
+    assert_eq!(header(&response, "content-type"), Some(MULTIPART));
+
+    let site = Site::with(&[("video.mp4", "0123456789")]);
+    let app = StaticRouter::new(site.path()).build();
+    let response = get_with(&app, "/video.mp4", &[("range", "bytes=0-1,3-4")]).await;
+    assert_eq!(response.status(), 416);
+}
+
+#[tokio::test]
+async fn disable_ranges_serves_whole_files() {
+    let site = Site::with(&[("video.mp4", "0123456789")]);
+    let app = StaticRouter::new(site.path()).disable_ranges(true).build();
+
+    let response = get_with(&app, "/video.mp4", &[("range", "bytes=0-3")]).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "accept-ranges"), None);
+    assert_eq!(header(&response, "content-range"), None);
+    assert_eq!(text(response).await, "0123456789");
+
+    let app = StaticRouter::new(site.path()).build();
+    let response = get_with(&app, "/video.mp4", &[("range", "bytes=0-3")]).await;
+    assert_eq!(response.status(), 206);
+    assert_eq!(text(response).await, "0123");
+}
+
+/// Returns the headers of a request for the first four bytes, if `etag` is current.
+fn if_range(etag: &str) -> [(&str, &str); 2] {
+    [("range", "bytes=0-3"), ("if-range", etag)]
+}
+
+#[tokio::test]
+async fn weak_if_range_tags_get_the_whole_file() {
+    let site = Site::with(&[("data.txt", "0123456789")]);
+
+    let app = StaticRouter::new(site.path()).etag(true).build();
+    let response = get_with(&app, "/data.txt", &[]).await;
+    let strong = header(&response, "etag").expect("ETag").to_owned();
+    assert!(strong.starts_with('"'), "{strong}");
+    let response = get_with(&app, "/data.txt", &if_range(&strong)).await;
+    assert_eq!(response.status(), 206);
+    assert_eq!(text(response).await, "0123");
+
+    let weak = format!("W/{strong}");
+    let response = get_with(&app, "/data.txt", &if_range(&weak)).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(text(response).await, "0123456789");
+
+    let app = StaticRouter::new(site.path())
+        .etag_content_only(true)
+        .build();
+    let response = get_with(&app, "/data.txt", &[]).await;
+    let weak = header(&response, "etag").expect("ETag").to_owned();
+    assert!(weak.starts_with("W/"), "{weak}");
+    let response = get_with(&app, "/data.txt", &if_range(&weak)).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "content-range"), None);
+    assert_eq!(text(response).await, "0123456789");
+}
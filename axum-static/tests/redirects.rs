@@ -0,0 +1,162 @@
+//! Redirects issued before serving files.
+
+mod common;
+
+use axum_static::StaticRouter;
+use common::{Site, get, get_with, header, text};
+
+#[tokio::test]
+async fn canonical_host_redirects_other_hosts() {
+    let site = Site::with(&[("docs/page.html", "page")]);
+    let app = StaticRouter::new(site.path())
+        .canonical_host("example.com")
+        .build();
+
+    let response = get_with(
+        &app,
+        "/docs/page.html?lang=en",
+        &[("host", "www.example.com")],
+    )
+    .await;
+    assert_eq!(response.status(), 301);
+    assert_eq!(
+        header(&response, "location"),
+        Some("//example.com/docs/page.html?lang=en")
+    );
+
+    let response = get_with(&app, "/docs/page.html", &[("host", "Example.COM")]).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(text(response).await, "page");
+}
+
+#[tokio::test]
+async fn redirects_file_applies_redirects_and_rewrites() {
+    let site = Site::with(&[
+        ("posts/index.html", "posts"),
+        ("app/index.html", "app shell"),
+        ("about.html", "about"),
+    ]);
+    let rules = Site::with(&[(
+        "_redirects",
+        "# moved sections\n/blog /posts/ 301\n\n/app /app/index.html 200\n",
+    )]);
+    let app = StaticRouter::new(site.path())
+        .redirects_file(rules.path().join("_redirects"))
+        .expect("valid rules")
+        .build();
+
+    let response = get(&app, "/blog").await;
+    assert_eq!(response.status(), 301);
+    assert_eq!(header(&response, "location"), Some("/posts/"));
+
+    let response = get(&app, "/app").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "content-type"), Some("text/html"));
+    assert_eq!(text(response).await, "app shell");
+
+    let response = get(&app, "/about.html").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(text(response).await, "about");
+}
+
+#[test]
+fn redirects_file_rejects_malformed_rules() {
+    let site = Site::new();
+    let rules = Site::with(&[("_redirects", "/old /new 404\n")]);
+    let err = StaticRouter::new(site.path())
+        .redirects_file(rules.path().join("_redirects"))
+        .expect_err("404 is not a redirect");
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[tokio::test]
+async fn root_redirect_sends_only_root_requests() {
+    let site = Site::with(&[
+        ("latest/index.html", "latest docs"),
+        ("about.html", "about"),
+    ]);
+    let app = StaticRouter::new(site.path())
+        .root_redirect("/latest/")
+        .build();
+
+    let response = get(&app, "/").await;
+    assert_eq!(response.status(), 302);
+    assert_eq!(header(&response, "location"), Some("/latest/"));
+
+    let response = get(&app, "/?ref=home").await;
+    assert_eq!(response.status(), 302);
+    assert_eq!(header(&response, "location"), Some("/latest/?ref=home"));
+
+    let response = get(&app, "/latest/").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(text(response).await, "latest docs");
+
+    let response = get(&app, "/about.html").await;
+    assert_eq!(response.status(), 200);
+}
+
+#[tokio::test]
+async fn redirect_loops_are_refused() {
+    let site = Site::with(&[("new.html", "new")]);
+    let rules = Site::with(&[(
+        "_redirects",
+        "/a /b 301\n/b /a 301\n/self /self?again 302\n/old /new.html 301\n",
+    )]);
+    let app = StaticRouter::new(site.path())
+        .redirects_file(rules.path().join("_redirects"))
+        .expect("valid rules")
+        .canonical_host("example.com")
+        .build();
+
+    for uri in ["/a", "/b", "/self"] {
+        let response = get_with(&app, uri, &[("host", "example.com")]).await;
+        assert_eq!(response.status(), 500, "{uri}");
+        assert_eq!(header(&response, "location"), None, "{uri}");
+    }
+
+    let response = get_with(&app, "/old", &[("host", "www.example.com")]).await;
+    assert_eq!(response.status(), 301);
+    assert_eq!(
+        header(&response, "location"),
+        Some("//example.com/new.html")
+    );
+
+    let response = get_with(&app, "/new.html", &[("host", "example.com")]).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(text(response).await, "new");
+}
+
+#[tokio::test]
+async fn redirects_to_directories_add_the_slash_in_one_pass() {
+    let site = Site::with(&[
+        ("docs/index.html", "docs"),
+        ("manual/index.html", "manual"),
+        ("latest/index.html", "latest"),
+    ]);
+    let rules = Site::with(&[(
+        "_redirects",
+        "/docs/ /docs 301\n/guide /manual 302\n/help /docs 302\n",
+    )]);
+    let app = StaticRouter::new(site.path())
+        .redirects_file(rules.path().join("_redirects"))
+        .expect("valid rules")
+        .root_redirect("/latest?from=root")
+        .build();
+
+    let response = get(&app, "/docs/").await;
+    assert_eq!(response.status(), 500);
+    assert_eq!(header(&response, "location"), None);
+
+    let response = get(&app, "/help").await;
+    assert_eq!(response.status(), 500, "/help leads into the /docs/ loop");
+
+    let response = get(&app, "/guide").await;
+    assert_eq!(response.status(), 302);
+    assert_eq!(header(&response, "location"), Some("/manual/"));
+
+    let response = get(&app, "/").await;
+    assert_eq!(response.status(), 302);
+    assert_eq!(header(&response, "location"), Some("/latest/?from=root"));
+    let response = get(&app, "/latest/").await;
+    assert_eq!(text(response).await, "latest");
+}
@@ -0,0 +1,140 @@
+//! Resolution of request paths to served files.
+
+mod common;
+
+use axum_static::{StaticRouter, spa_router, static_router_at};
+use common::{Site, get, header, text};
+
+#[tokio::test]
+async fn real_index_wins_over_spa_fallback() {
+    let site = Site::with(&[
+        ("index.html", "app shell"),
+        ("docs/index.html", "docs index"),
+        ("assets/app.js", "js"),
+    ]);
+    let app = StaticRouter::new(site.path()).spa_fallback(true).build();
+
+    let response = get(&app, "/docs/").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(text(response).await, "docs index");
+
+    let response = get(&app, "/dashboard/settings/profile").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "content-type"), Some("text/html"));
+    assert_eq!(text(response).await, "app shell");
+
+    let response = get(&app, "/assets/missing.js").await;
+    assert_eq!(response.status(), 404);
+
+    let response = get(&app, "/assets/app.js").await;
+    assert_eq!(header(&response, "content-type"), Some("text/javascript"));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn fifo_is_not_served() {
+    let site = Site::with(&[("file.txt", "regular")]);
+    let status = std::process::Command::new("mkfifo")
+        .arg(site.path().join("pipe.txt"))
+        .status()
+        .expect("run mkfifo");
+    assert!(status.success());
+    let app = StaticRouter::new(site.path())
+        .regular_files_only(true)
+        .build();
+
+    let response = tokio::time::timeout(std::time::Duration::from_secs(5), get(&app, "/pipe.txt"))
+        .await
+        .expect("FIFO request doesn't hang");
+    assert_eq!(response.status(), 404);
+
+    let response = get(&app, "/file.txt").await;
+    assert_eq!(response.status(), 200);
+}
+
+#[tokio::test]
+async fn nested_router_serves_subdirectory_index() {
+    let site = Site::with(&[("docs/index.html", "docs index")]);
+    let app = axum::Router::new().nest_service("/static", StaticRouter::new(site.path()).build());
+
+    let response = get(&app, "/static/docs/").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(text(response).await, "docs index");
+
+    let response = get(&app, "/static/docs").await;
+    assert!(response.status().is_redirection());
+    assert_eq!(header(&response, "location"), Some("/static/docs/"));
+}
+
+#[tokio::test]
+async fn directories_named_like_files_still_redirect() {
+    let site = Site::with(&[("v1.2/index.html", "release notes"), ("app.js", "js")]);
+    let app = StaticRouter::new(site.path()).build();
+
+    let response = get(&app, "/v1.2").await;
+    assert!(response.status().is_redirection());
+    assert_eq!(header(&response, "location"), Some("/v1.2/"));
+    assert_eq!(text(get(&app, "/v1.2/").await).await, "release notes");
+    assert_eq!(text(get(&app, "/app.js").await).await, "js");
+}
+
+#[tokio::test]
+async fn suggest_on_404_links_similar_files() {
+    let site = Site::with(&[("docs/app.js", "js"), ("docs/guide.html", "guide")]);
+    let app = StaticRouter::new(site.path()).suggest_on_404(true).build();
+
+    let response = get(&app, "/docs/app.jss").await;
+    assert_eq!(response.status(), 404);
+    assert_eq!(
+        header(&response, "content-type"),
+        Some("text/html; charset=utf-8")
+    );
+    let page = text(response).await;
+    assert!(page.contains(r#"<a href="app.js">app.js</a>"#), "{page}");
+    assert!(!page.contains("guide.html"), "{page}");
+
+    let response = get(&app, "/docs/unrelated.txt").await;
+    assert_eq!(response.status(), 404);
+    assert!(!text(response).await.contains("<a href"));
+}
+
+#[tokio::test]
+async fn spa_router_serves_the_shell_for_unmatched_routes() {
+    let site = Site::with(&[("index.html", "app shell"), ("assets/app.js", "js")]);
+    let app = spa_router(site.path());
+
+    let response = get(&app, "/dashboard/settings").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "content-type"), Some("text/html"));
+    assert_eq!(text(response).await, "app shell");
+
+    let response = get(&app, "/assets/app.js").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "content-type"), Some("text/javascript"));
+    assert_eq!(text(response).await, "js");
+
+    let empty = Site::new();
+    let response = get(&spa_router(empty.path()), "/dashboard").await;
+    assert_eq!(response.status(), 404);
+}
+
+#[tokio::test]
+async fn static_router_at_redirects_the_bare_prefix() {
+    let site = Site::with(&[("index.html", "home"), ("app.css", "a {}")]);
+    let app = axum::Router::new().nest("/a", static_router_at("/static", site.path()));
+
+    let response = get(&app, "/a/static/").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(text(response).await, "home");
+    let response = get(&app, "/a/static/app.css").await;
+    assert_eq!(header(&response, "content-type"), Some("text/css"));
+
+    let response = get(&app, "/a/static").await;
+    assert_eq!(response.status(), 307);
+    assert_eq!(header(&response, "location"), Some("/a/static/"));
+    let response = get(&app, "/a/static?v=1").await;
+    assert_eq!(header(&response, "location"), Some("/a/static/?v=1"));
+
+    let app = StaticRouter::new(site.path()).build();
+    assert_eq!(get(&app, "/").await.status(), 200);
+}
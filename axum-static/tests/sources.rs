@@ -0,0 +1,357 @@
+//! Custom file sources, retries and fallbacks on IO errors.
+
+mod common;
+
+use axum::body::{Body, Bytes};
+use axum_static::{AsyncFileSource, EmbeddedAssets, FileStat, SourceFuture, StaticRouter};
+use common::{Site, get, header, text};
+use http_body::Frame;
+use http_body_util::BodyExt;
+use std::collections::VecDeque;
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+/// A source of a single file whose first `failures` lookups fail with `kind`.
+struct Flaky {
+    failures: Arc<AtomicU32>,
+    kind: io::ErrorKind,
+    contents: &'static str,
+}
+
+impl Flaky {
+    fn new(failures: u32, kind: io::ErrorKind, contents: &'static str) -> Self {
+        Self {
+            failures: Arc::new(AtomicU32::new(failures)),
+            kind,
+            contents,
+        }
+    }
+}
+
+impl AsyncFileSource for Flaky {
+    fn stat<'a>(&'a self, _path: &'a str) -> SourceFuture<'a, Option<FileStat>> {
+        Box::pin(async move {
+            let failed = self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| {
+                    left.checked_sub(1)
+                })
+                .is_ok();
+            if failed {
+                return Err(self.kind.into());
+            }
+            Ok(Some(FileStat::new(self.contents.len() as u64)))
+        })
+    }
+
+    fn open<'a>(&'a self, _path: &'a str) -> SourceFuture<'a, Body> {
+        Box::pin(async move { Ok(Body::from(self.contents)) })
+    }
+}
+
+#[tokio::test]
+async fn read_retries_recover_from_transient_errors() {
+    let site = Site::new();
+    let source = Flaky::new(2, io::ErrorKind::Interrupted, "recovered");
+    let failures = Arc::clone(&source.failures);
+    let app = StaticRouter::new(site.path())
+        .file_source(source)
+        .read_retries(2)
+        .build();
+
+    let response = get(&app, "/file.txt").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(failures.load(Ordering::SeqCst), 0);
+    assert_eq!(text(response).await, "recovered");
+
+    let app = StaticRouter::new(site.path())
+        .file_source(Flaky::new(2, io::ErrorKind::Interrupted, "recovered"))
+        .read_retries(1)
+        .build();
+    let response = get(&app, "/file.txt").await;
+    assert_eq!(response.status(), 500);
+
+    let app = StaticRouter::new(site.path())
+        .file_source(Flaky::new(1, io::ErrorKind::PermissionDenied, "denied"))
+        .read_retries(3)
+        .build();
+    let response = get(&app, "/file.txt").await;
+    assert_eq!(response.status(), 500);
+}
+
+#[tokio::test]
+async fn failover_root_serves_when_the_primary_fails() {
+    let failover = Site::with(&[("file.txt", "from failover")]);
+    let app = StaticRouter::new(failover.path())
+        .file_source(Flaky::new(u32::MAX, io::ErrorKind::Other, "unreachable"))
+        .failover_root(failover.path())
+        .build();
+
+    let response = get(&app, "/file.txt").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "content-type"), Some("text/plain"));
+    assert_eq!(text(response).await, "from failover");
+
+    let response = get(&app, "/missing.txt").await;
+    assert_eq!(response.status(), 404);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn failover_root_serves_files_unreadable_in_the_primary_root() {
+    let primary = Site::new();
+    std::os::unix::fs::symlink("loop.txt", primary.path().join("loop.txt")).expect("symlink");
+    let failover = Site::with(&[("loop.txt", "from failover")]);
+
+    let app = StaticRouter::new(primary.path()).build();
+    assert_eq!(get(&app, "/loop.txt").await.status(), 500);
+
+    let app = StaticRouter::new(primary.path())
+        .failover_root(failover.path())
+        .build();
+    let response = get(&app, "/loop.txt").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(text(response).await, "from failover");
+}
+
+#[tokio::test]
+async fn on_disconnect_reports_partial_downloads() {
+    let site = Site::new();
+    site.file("large.bin", vec![7; 1 << 20]);
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let seen = Arc::clone(&reports);
+    let app = StaticRouter::new(site.path())
+        .on_disconnect(move |path, sent| seen.lock().unwrap().push((path.to_owned(), sent)))
+        .build();
+
+    let mut body = get(&app, "/large.bin").await.into_body();
+    let first = body
+        .frame()
+        .await
+        .expect("a frame")
+        .expect("readable")
+        .into_data()
+        .expect("data frame");
+    drop(body);
+    let sent = first.len() as u64;
+    assert!(0 < sent && sent < 1 << 20);
+    assert_eq!(*reports.lock().unwrap(), [("/large.bin".to_owned(), sent)]);
+
+    let body = common::bytes(get(&app, "/large.bin").await).await;
+    assert_eq!(body.len(), 1 << 20);
+    assert_eq!(reports.lock().unwrap().len(), 1);
+}
+
+/// A source of a single JSON array, sent in chunks of [`CHUNK`] bytes by a body that
+/// doesn't know its length up front.
+struct Streamed {
+    chunks: usize,
+}
+
+/// The bytes of each chunk streamed by [`Streamed`].
+const CHUNK: usize = 1 << 20;
+
+impl AsyncFileSource for Streamed {
+    fn stat<'a>(&'a self, _path: &'a str) -> SourceFuture<'a, Option<FileStat>> {
+        Box::pin(async move { Ok(Some(FileStat::new((self.chunks * CHUNK) as u64))) })
+    }
+
+    fn open<'a>(&'a self, _path: &'a str) -> SourceFuture<'a, Body> {
+        let chunks = (0..self.chunks)
+            .map(|index| {
+                let mut data = vec![b' '; CHUNK];
+                if index == 0 {
+                    data[0] = b'[';
+                }
+                if index == self.chunks - 1 {
+                    data[CHUNK - 1] = b']';
+                }
+                Bytes::from(data)
+            })
+            .collect();
+        Box::pin(async move { Ok(Body::new(Chunks(chunks))) })
+    }
+}
+
+/// A body sending its chunks in order, with no size hint.
+struct Chunks(VecDeque<Bytes>);
+
+impl http_body::Body for Chunks {
+    type Data = Bytes;
+    type Error = io::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, io::Error>>> {
+        Poll::Ready(self.0.pop_front().map(|chunk| Ok(Frame::data(chunk))))
+    }
+}
+
+#[tokio::test]
+async fn oversized_streamed_json_is_served_unwrapped() {
+    let site = Site::new();
+    let app = StaticRouter::new(site.path())
+        .file_source(Streamed { chunks: 17 })
+        .allow_jsonp(true)
+        .build();
+
+    let response = get(&app, "/data.json?callback=cb").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "content-type"), Some("application/json"));
+    let body = common::bytes(response).await;
+    assert_eq!(body.len(), 17 * CHUNK);
+    assert_eq!((body[0], body[body.len() - 1]), (b'[', b']'));
+
+    let app = StaticRouter::new(site.path())
+        .file_source(Streamed { chunks: 2 })
+        .allow_jsonp(true)
+        .build();
+    let response = get(&app, "/data.json?callback=cb").await;
+    assert!(common::bytes(response).await.starts_with(b"/**/cb(["));
+}
+
+#[tokio::test]
+async fn source_objects_are_labelled_and_streamed() {
+    let site = Site::new();
+    let app = StaticRouter::new(site.path())
+        .file_source(Streamed { chunks: 3 })
+        .build();
+
+    let response = get(&app, "/exports/data.json").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "content-type"), Some("application/json"));
+    let length = (3 * CHUNK).to_string();
+    assert_eq!(header(&response, "content-length"), Some(length.as_str()));
+
+    let mut body = response.into_body();
+    let mut frames = Vec::new();
+    while let Some(frame) = body.frame().await {
+        let data = frame.expect("readable").into_data().expect("data frame");
+        frames.push(data.len());
+    }
+    assert_eq!(frames, [CHUNK; 3]);
+}
+
+#[tokio::test]
+async fn json_errors_name_the_io_error_kind() {
+    let site = Site::new();
+    for (kind, name) in [
+        (io::ErrorKind::PermissionDenied, "PermissionDenied"),
+        (io::ErrorKind::TimedOut, "TimedOut"),
+    ] {
+        let app = StaticRouter::new(site.path())
+            .file_source(Flaky::new(1, kind, "unreachable"))
+            .json_errors(true)
+            .build();
+
+        let response = get(&app, "/secret/file.txt").await;
+        assert_eq!(response.status(), 500);
+        assert_eq!(header(&response, "content-type"), Some("application/json"));
+        let body = text(response).await;
+        assert_eq!(
+            body,
+            format!(r#"{{"status":500,"error":"Internal Server Error","kind":"{name}"}}"#)
+        );
+    }
+}
+
+#[tokio::test]
+async fn embedded_fallback_serves_files_missing_from_disk() {
+    let site = Site::with(&[("index.html", "from disk")]);
+    let assets = || {
+        EmbeddedAssets::new()
+            .file("index.html", b"embedded home")
+            .file("/errors/maintenance.html", b"<h1>Back soon</h1>")
+    };
+    let app = StaticRouter::new(site.path())
+        .embedded_fallback(assets())
+        .build();
+
+    let response = get(&app, "/").await;
+    assert_eq!(text(response).await, "from disk");
+    let response = get(&app, "/errors/maintenance.html").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "content-type"), Some("text/html"));
+    assert_eq!(text(response).await, "<h1>Back soon</h1>");
+    let response = get(&app, "/missing.html").await;
+    assert_eq!(response.status(), 404);
+
+    let app = StaticRouter::new(site.path().join("unmounted"))
+        .embedded_fallback(assets())
+        .build();
+    let response = get(&app, "/index.html").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(text(response).await, "embedded home");
+}
+
+/// A log writer appending to a shared buffer.
+#[cfg(feature = "tracing")]
+#[derive(Clone, Default)]
+struct Logs(Arc<Mutex<Vec<u8>>>);
+
+#[cfg(feature = "tracing")]
+impl Logs {
+    /// Returns everything logged so far.
+    fn text(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).expect("UTF-8 logs")
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl io::Write for Logs {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn broken_pipes_are_not_server_errors() {
+    #[cfg(feature = "tracing")]
+    let logs = Logs::default();
+    #[cfg(feature = "tracing")]
+    let _logging = {
+        let logs = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_ansi(false)
+            .with_writer(move || logs.clone())
+            .finish();
+        tracing::subscriber::set_default(subscriber)
+    };
+
+    let site = Site::new();
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let seen = Arc::clone(&reports);
+    let app = StaticRouter::new(site.path())
+        .file_source(Flaky::new(u32::MAX, io::ErrorKind::BrokenPipe, "gone"))
+        .json_errors(true)
+        .on_disconnect(move |path, sent| seen.lock().unwrap().push((path.to_owned(), sent)))
+        .build();
+
+    let response = get(&app, "/large.bin").await;
+    assert_eq!(response.status(), 499);
+    assert!(common::bytes(response).await.is_empty());
+    assert_eq!(*reports.lock().unwrap(), [("/large.bin".to_owned(), 0)]);
+    #[cfg(feature = "tracing")]
+    assert!(!logs.text().contains("ERROR"), "{}", logs.text());
+
+    let app = StaticRouter::new(site.path())
+        .file_source(Flaky::new(
+            u32::MAX,
+            io::ErrorKind::PermissionDenied,
+            "denied",
+        ))
+        .build();
+    let response = get(&app, "/large.bin").await;
+    assert_eq!(response.status(), 500);
+    #[cfg(all(feature = "tracing", feature = "handle_error"))]
+    assert!(logs.text().contains("ERROR"), "{}", logs.text());
+}
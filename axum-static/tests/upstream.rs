@@ -0,0 +1,62 @@
+//! Fetching files missing from the root from a CDN upstream.
+
+#![cfg(feature = "cdn_fallback")]
+
+mod common;
+
+use axum::{Router, body::Body, http::Request, http::Uri, routing};
+use axum_static::StaticRouter;
+use common::{Site, get, header, send, text};
+
+/// Starts an upstream echoing the path and query of each request, except for a
+/// `/secret` outside the CDN base path, and returns its base URL.
+async fn upstream() -> String {
+    let app = Router::new()
+        .route("/secret", routing::get(|| async { "secret" }))
+        .fallback(|uri: Uri| async move { ([("cache-control", "max-age=60")], uri.to_string()) });
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind upstream");
+    let address = listener.local_addr().expect("upstream address");
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    format!("http://{address}/assets/")
+}
+
+#[tokio::test]
+async fn missing_files_are_fetched_from_upstream() {
+    let site = Site::with(&[("local.js", "local")]);
+    let app = StaticRouter::new(site.path())
+        .cdn_fallback(&upstream().await)
+        .build();
+
+    let response = get(&app, "/app.js?v=1").await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(header(&response, "cache-control"), Some("max-age=60"));
+    assert_eq!(text(response).await, "/assets/app.js?v=1");
+
+    let response = get(&app, "/./lib/a%20b.js").await;
+    assert_eq!(text(response).await, "/assets/lib/a%20b.js");
+
+    let response = get(&app, "/local.js").await;
+    assert_eq!(text(response).await, "local");
+}
+
+#[tokio::test]
+async fn only_safe_requests_inside_the_root_are_proxied() {
+    let site = Site::new();
+    let app = StaticRouter::new(site.path())
+        .cdn_fallback(&upstream().await)
+        .build();
+
+    for uri in ["/../secret", "/lib/%2e%2e/%2e%2e/secret"] {
+        let response = get(&app, uri).await;
+        assert_eq!(response.status(), 404, "{uri}");
+        assert_ne!(text(response).await, "secret", "{uri}");
+    }
+
+    let request = Request::post("/app.js")
+        .body(Body::empty())
+        .expect("request");
+    let response = send(&app, request).await;
+    assert_eq!(response.status(), 405);
+}